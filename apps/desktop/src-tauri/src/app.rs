@@ -11,8 +11,10 @@ use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
 use echo_policy::{
-    CostModel, FixedScorer, InternalScorer, LinearScorer, RerollPolicySolver, SCORE_MULTIPLIER,
-    UpgradePolicySolver, bits_to_mask, mask_to_bits,
+    CostModel, EchoMask, FixedScorer, InternalScorer, LegacyCostWeights, LinearScorer,
+    LockChoiceView, RawScore, RerollPolicySolver, RerollPolicySolverBuilder, SCORE_MULTIPLIER,
+    UpgradePolicySolver, UpgradePolicySolverError, bits_to_mask,
+    buff_name as echo_policy_buff_name, buff_value_options, mask_to_bits, parse_echo,
 };
 use serde::{Deserialize, Serialize};
 use tauri::{Emitter, Manager, State};
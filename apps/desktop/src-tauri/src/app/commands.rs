@@ -4,3 +4,4 @@ include!("commands_ocr.rs");
 include!("commands_presets.rs");
 include!("commands_upgrade_policy.rs");
 include!("commands_reroll.rs");
+include!("commands_session.rs");
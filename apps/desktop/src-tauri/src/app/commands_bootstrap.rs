@@ -5,7 +5,7 @@ fn bootstrap() -> BootstrapResponse {
 
     for (index, buff_name) in BUFF_TYPES.iter().enumerate() {
         buff_labels.insert((*buff_name).to_string(), BUFF_LABELS[index].to_string());
-        value_options.insert((*buff_name).to_string(), BUFF_VALUE_OPTIONS[index].to_vec());
+        value_options.insert((*buff_name).to_string(), buff_value_options(index));
     }
 
     BootstrapResponse {
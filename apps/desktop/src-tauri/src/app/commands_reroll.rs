@@ -33,9 +33,10 @@ fn compute_reroll_policy(
             .ok_or_else(|| "Reroll solver session was not initialized".to_string())?;
         configure_and_derive_reroll_policy(&mut session.solver, payload.target_score)?;
     } else {
-        let mut solver = RerollPolicySolver::new(weights)
-            .map_err(|err| format!("Failed to create reroll solver: {err:?}"))?;
-        configure_and_derive_reroll_policy(&mut solver, payload.target_score)?;
+        let solver = RerollPolicySolverBuilder::new(weights)
+            .target(payload.target_score)
+            .build()
+            .map_err(|err| format!("Failed to create reroll policy: {err:?}"))?;
         let scorer =
             FixedScorer::new(weights).map_err(|err| format!("Invalid fixed scorer: {err:?}"))?;
         *current_reroll = Some(RerollSession {
@@ -85,8 +86,8 @@ fn query_reroll_recommendation(
         });
     }
 
-    let baseline_mask = build_full_mask(&payload.baseline_buff_names)?;
-    let baseline_score = fixed_score_from_selected(&session.scorer, &payload.baseline_buff_names)?;
+    let (baseline_mask, baseline_score) =
+        parse_full_echo(&session.scorer, &payload.baseline_buff_names)?;
 
     let default_top_k = default_reroll_top_k();
     let top_k = if payload.top_k == 0 {
@@ -94,27 +95,15 @@ fn query_reroll_recommendation(
     } else {
         payload.top_k.min(default_top_k)
     };
-    let choices = session
+    let baseline_names = library_buff_names(&payload.baseline_buff_names)?;
+    let recommended_lock_choices = session
         .solver
-        .lock_choices(baseline_mask, top_k)
+        .lock_choices_view(baseline_mask, top_k, &baseline_names)
         .map_err(|err| format!("Failed to query lock choices: {err:?}"))?;
-    let recommended_lock_choices = choices
-        .into_iter()
-        .map(|choice| RerollChoiceResponse {
-            lock_mask_bits: mask_to_bits(choice.lock_mask).to_vec(),
-            lock_slot_indices: lock_slot_indices_from_mask(
-                choice.lock_mask,
-                &payload.baseline_buff_names,
-            ),
-            expected_cost: choice.expected_cost,
-            regret: choice.regret,
-            success_probability: choice.success_probability,
-        })
-        .collect();
 
     let (candidate_score, accept_candidate) = if candidate_filled {
-        let candidate_mask = build_full_mask(&payload.candidate_buff_names)?;
-        let score = fixed_score_from_selected(&session.scorer, &payload.candidate_buff_names)?;
+        let (candidate_mask, score) =
+            parse_full_echo(&session.scorer, &payload.candidate_buff_names)?;
         let accept = session
             .solver
             .should_accept(baseline_mask, candidate_mask)
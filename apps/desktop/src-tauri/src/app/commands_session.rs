@@ -0,0 +1,145 @@
+#[tauri::command]
+fn save_session(state: State<'_, AppState>, payload: SaveSessionRequest) -> Result<(), String> {
+    let upgrade = {
+        let current_upgrade = state
+            .current_upgrade
+            .lock()
+            .map_err(|_| "Failed to lock current upgrade solver".to_string())?;
+        current_upgrade.as_ref().map(|session| UpgradeSessionFile {
+            scorer_config: session.scorer_config,
+            target_score: session.target_score,
+            blend_data: session.blend_data,
+            cost_weights: session.cost_weights,
+            exp_refund_ratio: session.exp_refund_ratio,
+            lambda: session.solver.lambda(),
+        })
+    };
+    let reroll = {
+        let current_reroll = state
+            .current_reroll
+            .lock()
+            .map_err(|_| "Failed to lock current reroll solver".to_string())?;
+        current_reroll
+            .as_ref()
+            .map(|session| {
+                let policy_bin = session
+                    .solver
+                    .serialize_policy_bin()
+                    .map_err(|err| format!("Failed to serialize reroll policy: {err:?}"))?;
+                Ok::<_, String>(RerollSessionFile {
+                    weights: session.weights,
+                    policy_bin,
+                })
+            })
+            .transpose()?
+    };
+
+    let file = SessionFile {
+        version: SESSION_FILE_VERSION,
+        upgrade,
+        reroll,
+    };
+    let content = serde_json::to_string(&file)
+        .map_err(|err| format!("Failed to serialize session: {err}"))?;
+    fs::write(&payload.path, content)
+        .map_err(|err| format!("Failed to write session file '{}': {err}", payload.path))
+}
+
+#[tauri::command]
+fn load_session(
+    state: State<'_, AppState>,
+    payload: LoadSessionRequest,
+) -> Result<LoadSessionResponse, String> {
+    let content = fs::read_to_string(&payload.path)
+        .map_err(|err| format!("Failed to read session file '{}': {err}", payload.path))?;
+    let header: SessionFileHeader = serde_json::from_str(&content)
+        .map_err(|err| format!("Invalid session file '{}': {err}", payload.path))?;
+    if header.version != SESSION_FILE_VERSION {
+        return Err(format!(
+            "Session file '{}' has version {}, but this app reads version {}",
+            payload.path, header.version, SESSION_FILE_VERSION
+        ));
+    }
+    let file: SessionFile = serde_json::from_str(&content)
+        .map_err(|err| format!("Invalid session file '{}': {err}", payload.path))?;
+
+    // Restore both sessions before touching the state so a bad file leaves it unchanged.
+    let (upgrade, upgrade_summary) = match file.upgrade {
+        Some(saved) => {
+            let (session, summary) = restore_upgrade_session(saved)?;
+            (Some(session), summary)
+        }
+        None => (None, None),
+    };
+    let reroll = file.reroll.map(restore_reroll_session).transpose()?;
+    let reroll_target_score = reroll
+        .as_ref()
+        .and_then(|session| session.solver.target_score());
+
+    let mut current_upgrade = state
+        .current_upgrade
+        .lock()
+        .map_err(|_| "Failed to lock current upgrade solver".to_string())?;
+    let mut current_reroll = state
+        .current_reroll
+        .lock()
+        .map_err(|_| "Failed to lock current reroll solver".to_string())?;
+    *current_upgrade = upgrade;
+    *current_reroll = reroll;
+
+    Ok(LoadSessionResponse {
+        upgrade_summary,
+        reroll_target_score,
+    })
+}
+
+fn restore_upgrade_session(
+    saved: UpgradeSessionFile,
+) -> Result<(SolverSession, Option<PolicySummary>), String> {
+    let cost_model = CostModel::new(
+        saved.cost_weights.w_echo,
+        saved.cost_weights.w_tuner,
+        saved.cost_weights.w_exp,
+        saved.exp_refund_ratio,
+    )
+    .map_err(|err| format!("Invalid cost model: {err:?}"))?;
+    let scorer = build_upgrade_scorer(&saved.scorer_config)?;
+    let (summary_target_score, solver_target_score) =
+        resolve_target_scores(&saved.scorer_config, &scorer, saved.target_score)?;
+    let solver = build_upgrade_solver(&scorer, saved.blend_data, solver_target_score, cost_model)?;
+    let mut session = SolverSession {
+        solver,
+        target_score: summary_target_score,
+        scorer_config: saved.scorer_config,
+        query_scorer: scorer,
+        blend_data: saved.blend_data,
+        cost_weights: saved.cost_weights,
+        exp_refund_ratio: saved.exp_refund_ratio,
+    };
+
+    let Some(lambda) = saved.lambda else {
+        return Ok((session, None));
+    };
+    if !lambda.is_finite() || lambda <= 0.0 {
+        return Err(format!("Invalid lambda in session file: {lambda}"));
+    }
+    let start = Instant::now();
+    session.solver.derive_policy_at_lambda(lambda);
+    let summary = summarize_upgrade_session(&mut session, lambda, start)?;
+    Ok((session, Some(summary)))
+}
+
+fn restore_reroll_session(saved: RerollSessionFile) -> Result<RerollSession, String> {
+    let mut solver = RerollPolicySolver::new(saved.weights)
+        .map_err(|err| format!("Failed to create reroll policy: {err:?}"))?;
+    solver
+        .load_policy_bin(&saved.policy_bin)
+        .map_err(|err| format!("Failed to load reroll policy: {err:?}"))?;
+    let scorer =
+        FixedScorer::new(saved.weights).map_err(|err| format!("Invalid fixed scorer: {err:?}"))?;
+    Ok(RerollSession {
+        solver,
+        weights: saved.weights,
+        scorer,
+    })
+}
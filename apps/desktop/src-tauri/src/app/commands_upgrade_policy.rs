@@ -17,13 +17,8 @@ fn compute_policy(
         w_exp: payload.cost_weights.w_exp,
     };
 
-    let cost_model = CostModel::new(
-        cost_weights.w_echo,
-        cost_weights.w_tuner,
-        cost_weights.w_exp,
-        exp_refund_ratio,
-    )
-    .map_err(|err| format!("Invalid cost model: {err:?}"))?;
+    let cost_model = CostModel::from_legacy_weights(payload.cost_weights, exp_refund_ratio)
+        .map_err(|err| format!("Invalid cost model: {err:?}"))?;
     let scorer_type = parse_scorer_type(&payload.scorer_type)?;
     let scorer_config = build_upgrade_scorer_config_from_inputs(
         scorer_type,
@@ -56,7 +51,7 @@ fn compute_policy(
             .ok_or_else(|| "Upgrade solver session was not initialized".to_string())?;
         session
             .solver
-            .update_target_score(solver_target_score)
+            .update_target_score_display(solver_target_score)
             .map_err(|err| format!("Failed to update target score: {err:?}"))?;
         session.target_score = summary_target_score;
     } else {
@@ -80,7 +75,18 @@ fn compute_policy(
     let lambda_star = session
         .solver
         .lambda_search(payload.lambda_tolerance, payload.lambda_max_iter)
-        .map_err(|err| format!("Failed during lambda search: {err:?}"))?;
+        .map_err(|err| format_lambda_search_error(&err))?;
+    let summary = summarize_upgrade_session(session, lambda_star, start)?;
+
+    Ok(ComputePolicyResponse { summary })
+}
+
+/// Computes expected resources for a derived policy; `start` is when the derivation began.
+fn summarize_upgrade_session(
+    session: &mut SolverSession,
+    lambda_star: f64,
+    start: Instant,
+) -> Result<PolicySummary, String> {
     let expected = session
         .solver
         .calculate_expected_resources()
@@ -90,9 +96,11 @@ fn compute_policy(
         .weighted_expected_cost()
         .map_err(|err| format!("Failed to compute weighted expected cost: {err:?}"))?;
     let compute_seconds = start.elapsed().as_secs_f64();
+    let cost_model = session.solver.cost_model();
+    let (w_echo, w_tuner, w_exp) = cost_model.weights();
 
-    let summary = PolicySummary {
-        target_score: summary_target_score,
+    Ok(PolicySummary {
+        target_score: session.target_score,
         lambda_star,
         expected_cost_per_success,
         compute_seconds,
@@ -100,11 +108,13 @@ fn compute_policy(
         echo_per_success: expected.echo_per_success(),
         tuner_per_success: expected.tuner_per_success(),
         exp_per_success: expected.exp_per_success(),
-        cost_weights,
-        exp_refund_ratio,
-    };
-
-    Ok(ComputePolicyResponse { summary })
+        cost_weights: CostWeightsOutput {
+            w_echo,
+            w_tuner,
+            w_exp,
+        },
+        exp_refund_ratio: cost_model.exp_refund_ratio(),
+    })
 }
 
 #[tauri::command]
@@ -125,6 +135,7 @@ fn policy_suggestion(
     })?;
 
     let mask = build_mask(&payload.buff_names)?;
+    let echo_mask = to_echo_mask(mask)?;
     let score_scaled = if !payload.buff_names.is_empty() {
         score_from_selected_buffs_for_solver(
             &session.query_scorer,
@@ -140,12 +151,12 @@ fn policy_suggestion(
     } else {
         session
             .solver
-            .get_decision(mask, score_scaled)
+            .get_decision(echo_mask, RawScore::new(score_scaled))
             .map_err(|err| format!("Failed to query suggestion: {err:?}"))?
     };
     let success_probability = session
         .solver
-        .get_success_probability(mask, score_scaled)
+        .get_success_probability(echo_mask, RawScore::new(score_scaled))
         .map_err(|err| format!("Failed to query success probability: {err:?}"))?;
 
     Ok(PolicySuggestionResponse {
@@ -161,3 +172,68 @@ fn policy_suggestion(
     })
 }
 
+#[tauri::command]
+fn query_success_probability(
+    state: State<'_, AppState>,
+    payload: QuerySuccessProbabilityRequest,
+) -> Result<f64, String> {
+    let mask = to_echo_mask(build_mask_from_bits(&payload.state_bits)?)?;
+
+    let current_upgrade = state
+        .current_upgrade
+        .lock()
+        .map_err(|_| "Failed to lock current upgrade solver".to_string())?;
+    let session = current_upgrade.as_ref().ok_or_else(|| {
+        "No computed upgrade policy in memory. Please compute policy first.".to_string()
+    })?;
+
+    session
+        .solver
+        .get_success_probability(mask, RawScore::new(payload.raw_score))
+        .map_err(|err| format!("Failed to query success probability: {err:?}"))
+}
+
+#[tauri::command]
+fn get_policy_chart(state: State<'_, AppState>) -> Result<PolicyChartResponse, String> {
+    let current_upgrade = state
+        .current_upgrade
+        .lock()
+        .map_err(|_| "Failed to lock current upgrade solver".to_string())?;
+    let session = current_upgrade.as_ref().ok_or_else(|| {
+        "No computed upgrade policy in memory. Please compute policy first.".to_string()
+    })?;
+
+    let (_, solver_target_score) = resolve_target_scores(
+        &session.scorer_config,
+        &session.query_scorer,
+        session.target_score,
+    )?;
+    let score_multiplier = session.solver.score_multiplier();
+    let thresholds = session
+        .solver
+        .threshold_curve()
+        .map_err(|err| format!("Failed to query threshold curve: {err:?}"))?
+        .iter()
+        .map(|threshold| threshold.map(|score| f64::from(score) / score_multiplier))
+        .collect();
+
+    Ok(PolicyChartResponse {
+        target_score: session.target_score,
+        solver_target_score,
+        thresholds,
+    })
+}
+
+fn format_lambda_search_error(err: &UpgradePolicySolverError) -> String {
+    match err {
+        UpgradePolicySolverError::LambdaNotFoundWithinMaxIter {
+            last_candidate,
+            last_advantage,
+            bracket: (lo, hi),
+        } => format!(
+            "Failed during lambda search: no root within lambdaMaxIter iterations \
+             (last lambda={last_candidate}, advantage={last_advantage}, bracket=[{lo}, {hi}])"
+        ),
+        _ => format!("Failed during lambda search: {err:?}"),
+    }
+}
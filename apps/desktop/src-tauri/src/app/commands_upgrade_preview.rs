@@ -53,3 +53,32 @@ fn preview_upgrade_score(
     }
 }
 
+#[tauri::command]
+fn weight_limits(payload: WeightLimitsRequest) -> Result<WeightLimitsResponse, String> {
+    let scorer_type = parse_scorer_type(&payload.scorer_type)?;
+    let scorer_config = build_upgrade_scorer_config_from_inputs(
+        scorer_type,
+        &payload.buff_weights,
+        payload.main_buff_score,
+        payload.normalized_max_score,
+    )?;
+    let scorer = build_upgrade_scorer(&scorer_config)?;
+
+    let solver_max_score = match &scorer {
+        UpgradeScorer::Linear(linear) => linear.max_echo_score(),
+        UpgradeScorer::Fixed(fixed) => f64::from(fixed.max_score()),
+    };
+    let max_echo_score = match &scorer_config {
+        UpgradeScorerConfig::QQBot {
+            normalized_max_score,
+            ..
+        } => solver_max_score * *normalized_max_score / DEFAULT_QQ_BOT_NORMALIZED_MAX_SCORE,
+        _ => solver_max_score,
+    };
+    let (_, max_target_score) = resolve_target_scores(&scorer_config, &scorer, max_echo_score)?;
+
+    Ok(WeightLimitsResponse {
+        max_echo_score,
+        max_target_score,
+    })
+}
@@ -12,10 +12,15 @@ pub(crate) fn run() {
             delete_scorer_preset,
             delete_scorer_preset_variant,
             preview_upgrade_score,
+            weight_limits,
             compute_policy,
             policy_suggestion,
+            query_success_probability,
+            get_policy_chart,
             compute_reroll_policy,
-            query_reroll_recommendation
+            query_reroll_recommendation,
+            save_session,
+            load_session
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
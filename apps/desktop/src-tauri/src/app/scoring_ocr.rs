@@ -29,7 +29,7 @@ fn parse_ocr_udp_payload(raw_message: &str) -> Result<OcrFillEntriesEvent, Strin
                 BUFF_TYPES[buff_idx]
             ));
         }
-        if !BUFF_VALUE_OPTIONS[buff_idx].contains(&entry.buff_value) {
+        if !buff_value_options(buff_idx).contains(&entry.buff_value) {
             return Err(format!(
                 "Invalid value {} for buff {}",
                 entry.buff_value, BUFF_TYPES[buff_idx]
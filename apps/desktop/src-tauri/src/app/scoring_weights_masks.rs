@@ -96,37 +96,46 @@ fn build_mask(buff_names: &[String]) -> Result<u16, String> {
     Ok(bits_to_mask(&bits))
 }
 
-fn build_full_mask(buff_names: &[String]) -> Result<u16, String> {
-    if buff_names.len() != MAX_SELECTED_TYPES {
+fn build_mask_from_bits(bits: &[u8]) -> Result<u16, String> {
+    if bits.len() != NUM_BUFFS {
         return Err(format!(
-            "Exactly {MAX_SELECTED_TYPES} buff types are required, got {}",
-            buff_names.len()
+            "Expected {NUM_BUFFS} state bits, got {}",
+            bits.len()
         ));
     }
-    let mask = build_mask(buff_names)?;
-    if mask.count_ones() as usize != MAX_SELECTED_TYPES {
-        return Err("Buff selections must be unique and fully filled".to_string());
+    if let Some(bit) = bits.iter().find(|&&bit| bit > 1) {
+        return Err(format!("State bits must be 0 or 1, got {bit}"));
+    }
+    let mask = bits_to_mask(bits);
+    if mask.count_ones() as usize > MAX_SELECTED_TYPES {
+        return Err(format!(
+            "Too many selected buffs: {}, max is {MAX_SELECTED_TYPES}",
+            mask.count_ones()
+        ));
     }
     Ok(mask)
 }
 
-fn fixed_score_from_selected(scorer: &FixedScorer, buff_names: &[String]) -> Result<u16, String> {
-    let zero_values = vec![0u16; buff_names.len()];
-    let indexed = build_indexed_echo(buff_names, &zero_values)?;
-    scorer
-        .echo_score_display(&indexed)
-        .map_err(|err| format!("Failed to compute fixed display score: {err:?}"))
+fn to_echo_mask(mask: u16) -> Result<EchoMask, String> {
+    EchoMask::new(mask).ok_or_else(|| format!("Invalid buff mask: {mask:#015b}"))
 }
 
-fn lock_slot_indices_from_mask(lock_mask: u16, baseline_buff_names: &[String]) -> Vec<usize> {
-    let mut slots = Vec::new();
-    for (slot_idx, buff_name) in baseline_buff_names.iter().enumerate() {
-        if let Some(buff_idx) = buff_index(buff_name) {
-            if (lock_mask & (1u16 << buff_idx)) != 0 {
-                slots.push(slot_idx + 1);
-            }
-        }
-    }
-    slots
+/// The `echo_policy::buff_name`s of a buff selection.
+fn library_buff_names(buff_names: &[String]) -> Result<Vec<&'static str>, String> {
+    buff_names
+        .iter()
+        .map(|buff_name| {
+            buff_index(buff_name)
+                .and_then(echo_policy_buff_name)
+                .ok_or_else(|| format!("Unknown buff name in selection: {buff_name}"))
+        })
+        .collect()
+}
+
+/// The full mask and fixed score of a 5-buff selection, via `echo_policy::parse_echo`.
+fn parse_full_echo(scorer: &FixedScorer, buff_names: &[String]) -> Result<(u16, u16), String> {
+    let library_names = library_buff_names(buff_names)?;
+    let zero_values = vec![0u16; buff_names.len()];
+    parse_echo(&library_names, &zero_values, scorer).map_err(|err| format!("Invalid echo: {err:?}"))
 }
 
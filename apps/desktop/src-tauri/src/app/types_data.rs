@@ -2,3 +2,4 @@ include!("types_data_presets.rs");
 include!("types_data_upgrade.rs");
 include!("types_data_reroll.rs");
 include!("types_data_ocr.rs");
+include!("types_data_session.rs");
@@ -0,0 +1,40 @@
+/// Read before `SessionFile` so a version mismatch is reported instead of a parse error.
+#[derive(Debug, Deserialize)]
+struct SessionFileHeader {
+    version: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionFile {
+    version: u32,
+    upgrade: Option<UpgradeSessionFile>,
+    reroll: Option<RerollSessionFile>,
+}
+
+/// The upgrade solver has no binary format; it is rebuilt from its inputs and the policy is
+/// derived again at the saved lambda.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpgradeSessionFile {
+    scorer_config: UpgradeScorerConfig,
+    target_score: f64,
+    blend_data: bool,
+    cost_weights: CostWeightsOutput,
+    exp_refund_ratio: f64,
+    lambda: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RerollSessionFile {
+    weights: [u16; NUM_BUFFS],
+    policy_bin: Vec<u8>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LoadSessionResponse {
+    upgrade_summary: Option<PolicySummary>,
+    reroll_target_score: Option<u16>,
+}
@@ -68,3 +68,18 @@ struct UpgradeScorePreviewResponse {
     total_score: f64,
     max_score: f64,
 }
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PolicyChartResponse {
+    target_score: f64,
+    solver_target_score: f64,
+    thresholds: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WeightLimitsResponse {
+    max_echo_score: f64,
+    max_target_score: f64,
+}
@@ -2,3 +2,4 @@ include!("types_requests_common.rs");
 include!("types_requests_upgrade.rs");
 include!("types_requests_reroll_ocr.rs");
 include!("types_requests_presets.rs");
+include!("types_requests_session.rs");
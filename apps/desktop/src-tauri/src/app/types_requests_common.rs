@@ -1,15 +1,4 @@
-#[derive(Debug, Deserialize, Default)]
-#[serde(rename_all = "camelCase")]
-struct CostWeightsInput {
-    #[serde(default)]
-    w_echo: f64,
-    #[serde(default, alias = "wDkq")]
-    w_tuner: f64,
-    #[serde(default)]
-    w_exp: f64,
-}
-
-#[derive(Debug, Serialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 #[serde(rename_all = "camelCase")]
 struct CostWeightsOutput {
     w_echo: f64,
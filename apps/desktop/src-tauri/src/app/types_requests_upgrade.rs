@@ -11,7 +11,7 @@ struct ComputePolicyRequest {
     #[serde(default)]
     normalized_max_score: Option<f64>,
     #[serde(default)]
-    cost_weights: CostWeightsInput,
+    cost_weights: LegacyCostWeights,
     exp_refund_ratio: Option<f64>,
     #[serde(default)]
     blend_data: bool,
@@ -30,6 +30,13 @@ struct PolicySuggestionRequest {
     buff_values: Vec<u16>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuerySuccessProbabilityRequest {
+    state_bits: Vec<u8>,
+    raw_score: u16,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct UpgradeScorePreviewRequest {
@@ -46,3 +53,16 @@ struct UpgradeScorePreviewRequest {
     #[serde(default)]
     buff_values: Vec<u16>,
 }
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WeightLimitsRequest {
+    #[serde(default)]
+    buff_weights: HashMap<String, f64>,
+    #[serde(default = "default_scorer_type")]
+    scorer_type: String,
+    #[serde(default)]
+    main_buff_score: Option<f64>,
+    #[serde(default)]
+    normalized_max_score: Option<f64>,
+}
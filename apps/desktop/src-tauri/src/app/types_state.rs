@@ -1,4 +1,4 @@
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 enum UpgradeScorerConfig {
     LinearDefault {
         weights: [f64; NUM_BUFFS],
@@ -6,6 +6,7 @@ pub(crate) const DEFAULT_MC_BOOST_ASSISTANT_TARGET_SCORE: f64 = 95.0;
 pub(crate) const DEFAULT_QQ_BOT_TARGET_SCORE: f64 = 35.0;
 pub(crate) const DEFAULT_EXP_REFUND_RATIO: f64 = 0.66;
 pub(crate) const DEFAULT_SCORER_TYPE: &str = "linear_default";
+pub(crate) const SESSION_FILE_VERSION: u32 = 1;
 
 pub(crate) const SCORER_TYPE_LINEAR_DEFAULT: &str = "linear_default";
 pub(crate) const SCORER_TYPE_WUWA_ECHO_TOOL: &str = "wuwa_echo_tool";
@@ -40,6 +41,8 @@ pub(crate) const OCR_UDP_EVENT_LISTENER_STATUS: &str = "ocr_udp_listener_status"
 pub(crate) const OCR_UDP_PACKET_BUFFER_SIZE: usize = 16 * 1024;
 pub(crate) const OCR_UDP_READ_TIMEOUT_MS: u64 = 300;
 
+// Serialization keys used by presets and the frontend, in `echo_policy::buff_names` order.
+// They differ from the library's display names, so they are not derived from it.
 pub(crate) const BUFF_TYPES: [&str; NUM_BUFFS] = [
     "Crit_Rate",
     "Crit_Damage",
@@ -94,33 +97,3 @@ pub(crate) const DEFAULT_QQ_BOT_BUFF_WEIGHTS: [f64; NUM_BUFFS] = [
 
 pub(crate) const DEFAULT_FIXED_BUFF_WEIGHTS: [u16; NUM_BUFFS] =
     [3, 3, 1, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0];
-
-const VALUES_CRIT_RATE: [u16; 8] = [63, 69, 75, 81, 87, 93, 99, 105];
-const VALUES_CRIT_DAMAGE: [u16; 8] = [126, 138, 150, 162, 174, 186, 198, 210];
-const VALUES_ATTACK: [u16; 8] = [64, 71, 79, 86, 94, 101, 109, 116];
-const VALUES_DEFENCE: [u16; 8] = [81, 90, 100, 109, 118, 128, 138, 147];
-const VALUES_HP: [u16; 8] = [64, 71, 79, 86, 94, 101, 109, 116];
-const VALUES_ATTACK_FLAT: [u16; 4] = [30, 40, 50, 60];
-const VALUES_DEFENCE_FLAT: [u16; 4] = [40, 50, 60, 70];
-const VALUES_HP_FLAT: [u16; 8] = [320, 360, 390, 430, 470, 510, 540, 580];
-const VALUES_ER: [u16; 8] = [68, 76, 84, 92, 100, 108, 116, 124];
-const VALUES_BASIC: [u16; 8] = [64, 71, 79, 86, 94, 101, 109, 116];
-const VALUES_HEAVY: [u16; 8] = [64, 71, 79, 86, 94, 101, 109, 116];
-const VALUES_SKILL: [u16; 8] = [64, 71, 79, 86, 94, 101, 109, 116];
-const VALUES_ULT: [u16; 8] = [64, 71, 79, 86, 94, 101, 109, 116];
-
-pub(crate) const BUFF_VALUE_OPTIONS: [&[u16]; NUM_BUFFS] = [
-    &VALUES_CRIT_RATE,
-    &VALUES_CRIT_DAMAGE,
-    &VALUES_ATTACK,
-    &VALUES_DEFENCE,
-    &VALUES_HP,
-    &VALUES_ATTACK_FLAT,
-    &VALUES_DEFENCE_FLAT,
-    &VALUES_HP_FLAT,
-    &VALUES_ER,
-    &VALUES_BASIC,
-    &VALUES_HEAVY,
-    &VALUES_SKILL,
-    &VALUES_ULT,
-];
@@ -297,10 +297,10 @@ fn prompt_f64_in_range(
     loop {
         let input = prompt_line(prompt, hint)?;
         let trimmed = input.trim();
-        if trimmed.is_empty() {
-            if let Some(value) = default {
-                return Ok(value);
-            }
+        if trimmed.is_empty()
+            && let Some(value) = default
+        {
+            return Ok(value);
         }
         match trimmed.parse::<f64>() {
             Ok(value) if value.is_finite() && (min..=max).contains(&value) => return Ok(value),
@@ -236,7 +236,7 @@ fn run() -> Result<(), RunError> {
             .resolve_solver_target_score(&scorer, target_score);
         if index > 0 {
             solver
-                .update_target_score(solver_target_score)
+                .update_target_score_display(solver_target_score)
                 .map_err(|err| {
                     RunError::Execution(format!(
                         "failed to update target score {target_score}: {err:?}"
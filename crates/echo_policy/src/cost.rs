@@ -1,4 +1,6 @@
-use crate::data::NUM_ECHO_SLOTS;
+use serde::Deserialize;
+
+use crate::data::{NUM_BUFFS, NUM_ECHO_SLOTS};
 
 const ECHO_COST: f64 = 1.0;
 
@@ -25,15 +27,76 @@ const EXP_INCREMENTAL_COSTS: [f64; NUM_ECHO_SLOTS] = [
 const EXP_REFUND_RATIO_DEFAULT: f64 = 0.66;
 const EXP_REFUND_RATIO_MAX: f64 = 0.75;
 
-// Shell credit cost not considered.
+// Shell credits are not part of the weighted cost, see `CostModel::shell_credit_cost`.
 // Each (raw) Echo EXP requires 0.1 Shell Credit.
 // Each tune attempt requires 2000 Shell Credit.
+const SHELL_CREDIT_PER_RAW_EXP: f64 = 0.1;
+const SHELL_CREDIT_PER_TUNE: f64 = 2000.0;
 
 #[derive(Debug)]
 pub enum CostModelError {
     NegativeWeight { field: &'static str, value: f64 },
     AllWeightsZero,
     InvalidExpRefundRatio { value: f64 },
+    InvalidBuffRevealMultiplier { buff_index: usize, value: f64 },
+    InvalidAbandonRefundRatio { value: f64 },
+    InvalidEchoAcquisitionCost { value: f64 },
+    InvalidRerollUnitCost { value: f64 },
+}
+
+impl std::fmt::Display for CostModelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NegativeWeight { field, value } => {
+                write!(
+                    f,
+                    "weight {field} must be a non-negative number, got {value}"
+                )
+            }
+            Self::AllWeightsZero => write!(f, "at least one cost weight must be positive"),
+            Self::InvalidExpRefundRatio { value } => {
+                write!(
+                    f,
+                    "exp refund ratio must be in [0, {EXP_REFUND_RATIO_MAX}], got {value}"
+                )
+            }
+            Self::InvalidBuffRevealMultiplier { buff_index, value } => write!(
+                f,
+                "reveal multiplier of buff {buff_index} must be a non-negative number, got {value}"
+            ),
+            Self::InvalidAbandonRefundRatio { value } => {
+                write!(f, "abandon refund ratio must be in [0, 1], got {value}")
+            }
+            Self::InvalidEchoAcquisitionCost { value } => {
+                write!(
+                    f,
+                    "echo acquisition cost must be a non-negative number, got {value}"
+                )
+            }
+            Self::InvalidRerollUnitCost { value } => {
+                write!(
+                    f,
+                    "reroll unit cost must be a non-negative number, got {value}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CostModelError {}
+
+/// Cost weights as sent by the desktop frontend (camelCase, missing fields are 0).
+///
+/// `wDkq` is a legacy name of `wTuner` that older clients still send.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegacyCostWeights {
+    #[serde(default)]
+    pub w_echo: f64,
+    #[serde(default, alias = "wDkq")]
+    pub w_tuner: f64,
+    #[serde(default)]
+    pub w_exp: f64,
 }
 
 #[derive(Clone, Copy)]
@@ -43,6 +106,15 @@ pub struct CostModel {
     weight_exp: f64,
     exp_refund_ratio: f64,
 
+    // Scales the reveal cost by the buff that gets revealed.
+    buff_reveal_multipliers: [f64; NUM_BUFFS],
+    // The part of the tuner/exp spent on a discarded echo that is paid back.
+    abandon_refund_ratio: f64,
+    // Weighted cost of obtaining each echo that is started, e.g. stamina.
+    echo_acquisition_cost: f64,
+    // Weighted cost of one unit of reroll lock cost.
+    reroll_unit_cost: f64,
+
     // Cached costs
     reveal_cost_cached: [f64; NUM_ECHO_SLOTS],
 }
@@ -136,10 +208,28 @@ impl CostModel {
             weight_tuner,
             weight_exp,
             exp_refund_ratio,
+            buff_reveal_multipliers: [1.0; NUM_BUFFS],
+            abandon_refund_ratio: 0.0,
+            echo_acquisition_cost: 0.0,
+            reroll_unit_cost: 1.0,
             reveal_cost_cached,
         }
     }
 
+    /// Create a cost model from weights sent by the desktop frontend, where the tuner
+    /// weight may have arrived as the legacy `wDkq` field.
+    pub fn from_legacy_weights(
+        weights: LegacyCostWeights,
+        exp_refund_ratio: f64,
+    ) -> Result<Self, CostModelError> {
+        Self::new(
+            weights.w_echo,
+            weights.w_tuner,
+            weights.w_exp,
+            exp_refund_ratio,
+        )
+    }
+
     /// Create a cost model with only weight_tuner=1.0
     pub fn tuner_only() -> Self {
         Self::build_cached(0.0, 1.0, 0.0, EXP_REFUND_RATIO_DEFAULT)
@@ -159,10 +249,98 @@ impl CostModel {
         let exp_refund_ratio = new_exp_refund_ratio.unwrap_or(self.exp_refund_ratio);
 
         Self::validate_weights(weight_echo, weight_tuner, weight_exp, exp_refund_ratio)?;
+        let buff_reveal_multipliers = self.buff_reveal_multipliers;
+        let abandon_refund_ratio = self.abandon_refund_ratio;
+        let echo_acquisition_cost = self.echo_acquisition_cost;
+        let reroll_unit_cost = self.reroll_unit_cost;
         *self = Self::build_cached(weight_echo, weight_tuner, weight_exp, exp_refund_ratio);
+        self.buff_reveal_multipliers = buff_reveal_multipliers;
+        self.abandon_refund_ratio = abandon_refund_ratio;
+        self.echo_acquisition_cost = echo_acquisition_cost;
+        self.reroll_unit_cost = reroll_unit_cost;
+        Ok(())
+    }
+
+    /// A copy with a different exp refund ratio, keeping the weights and other settings.
+    pub fn clone_with_refund(&self, exp_refund_ratio: f64) -> Result<Self, CostModelError> {
+        let mut cost_model = *self;
+        cost_model.update_weights(None, None, None, Some(exp_refund_ratio))?;
+        Ok(cost_model)
+    }
+
+    /// Pay back `ratio` of the tuner and exp spent on an echo that is not kept (0 by default).
+    ///
+    /// This only affects `UpgradePolicySolver::calculate_expected_resources`; the derived
+    /// policy, `weighted_expected_cost` and the naive and fixed threshold baselines ignore it.
+    pub fn set_abandon_refund_ratio(&mut self, ratio: f64) -> Result<(), CostModelError> {
+        if !ratio.is_finite() || !(0.0..=1.0).contains(&ratio) {
+            return Err(CostModelError::InvalidAbandonRefundRatio { value: ratio });
+        }
+        self.abandon_refund_ratio = ratio;
+        Ok(())
+    }
+
+    pub fn abandon_refund_ratio(&self) -> f64 {
+        self.abandon_refund_ratio
+    }
+
+    /// Charge `cost` once for every echo that is started (0 by default), in the same units
+    /// as the weighted cost. Unlike the echo weight it ignores the buff reveal multipliers.
+    pub fn set_echo_acquisition_cost(&mut self, cost: f64) -> Result<(), CostModelError> {
+        if !cost.is_finite() || cost < 0.0 {
+            return Err(CostModelError::InvalidEchoAcquisitionCost { value: cost });
+        }
+        self.echo_acquisition_cost = cost;
+        Ok(())
+    }
+
+    pub fn echo_acquisition_cost(&self) -> f64 {
+        self.echo_acquisition_cost
+    }
+
+    /// The weighted cost of one unit of `RerollPolicySolver` lock cost (1.0 by default,
+    /// i.e. lock costs are already weighted). Only used by `combined_expected_cost`.
+    pub fn set_reroll_unit_cost(&mut self, cost: f64) -> Result<(), CostModelError> {
+        if !cost.is_finite() || cost < 0.0 {
+            return Err(CostModelError::InvalidRerollUnitCost { value: cost });
+        }
+        self.reroll_unit_cost = cost;
+        Ok(())
+    }
+
+    pub fn reroll_unit_cost(&self) -> f64 {
+        self.reroll_unit_cost
+    }
+
+    /// Scale the weighted reveal cost by the buff that gets revealed (all 1.0 by default).
+    ///
+    /// Multipliers must be finite and >= 0.
+    pub fn set_buff_reveal_multipliers(
+        &mut self,
+        multipliers: [f64; NUM_BUFFS],
+    ) -> Result<(), CostModelError> {
+        for (buff_index, &value) in multipliers.iter().enumerate() {
+            if !value.is_finite() || value < 0.0 {
+                return Err(CostModelError::InvalidBuffRevealMultiplier { buff_index, value });
+            }
+        }
+        self.buff_reveal_multipliers = multipliers;
         Ok(())
     }
 
+    pub fn buff_reveal_multiplier(&self, buff_index: usize) -> f64 {
+        self.buff_reveal_multipliers[buff_index]
+    }
+
+    /// The `(echo, tuner, exp)` weights currently in effect.
+    pub fn weights(&self) -> (f64, f64, f64) {
+        (self.weight_echo, self.weight_tuner, self.weight_exp)
+    }
+
+    pub fn exp_refund_ratio(&self) -> f64 {
+        self.exp_refund_ratio
+    }
+
     pub fn tuner_cost(&self) -> f64 {
         (1.0 - TUNER_REFUND_RATIO) * TUNER_COST
     }
@@ -175,12 +353,46 @@ impl CostModel {
     ///
     /// Must ensure `current_slot` is in 0..=5
     pub fn full_upgrade_exp_cost(&self, current_slot: usize) -> f64 {
+        self.upgrade_exp_cost(current_slot, NUM_ECHO_SLOTS)
+    }
+
+    /// The `(tuner, exp)` to reveal every slot after `current_slot`, net of the refunds.
+    /// Keeping the echo additionally pays back the `success_additional_*_cost`.
+    ///
+    /// Must ensure `current_slot` is in 0..=5
+    pub fn full_upgrade_cost(&self, current_slot: usize) -> (f64, f64) {
+        let tuner = (NUM_ECHO_SLOTS - current_slot) as f64 * self.tuner_cost();
+        (tuner, self.full_upgrade_exp_cost(current_slot))
+    }
+
+    /// Calculate the exp cost for upgrading from current_slot until final_slot is revealed
+    ///
+    /// Must ensure `current_slot <= final_slot` and `final_slot` is in 1..=5
+    pub fn upgrade_exp_cost(&self, current_slot: usize, final_slot: usize) -> f64 {
+        let exp_now = if current_slot == 0 {
+            0.0
+        } else {
+            EXP_COST_BY_LEVEL[current_slot - 1]
+        };
+        (1.0 - self.exp_refund_ratio) * (EXP_COST_BY_LEVEL[final_slot - 1] - exp_now)
+    }
+
+    /// The raw EXP fed to upgrade from `current_slot` until `final_slot` is revealed,
+    /// before any refund.
+    ///
+    /// Must ensure `current_slot <= final_slot` and `final_slot` is in 1..=5
+    pub fn raw_upgrade_exp(&self, current_slot: usize, final_slot: usize) -> f64 {
         let exp_now = if current_slot == 0 {
             0.0
         } else {
             EXP_COST_BY_LEVEL[current_slot - 1]
         };
-        (1.0 - self.exp_refund_ratio) * (EXP_COST_BY_LEVEL[NUM_ECHO_SLOTS - 1] - exp_now)
+        (EXP_COST_BY_LEVEL[final_slot - 1] - exp_now) * EXP_PER_TUBE
+    }
+
+    /// The shell credits paid for feeding `raw_exp` raw EXP and making `tuner_attempts` tunes.
+    pub fn shell_credit_cost(&self, raw_exp: f64, tuner_attempts: f64) -> f64 {
+        SHELL_CREDIT_PER_RAW_EXP * raw_exp + SHELL_CREDIT_PER_TUNE * tuner_attempts
     }
 
     /// The weighted cost to reveal `slot`.
@@ -188,19 +400,85 @@ impl CostModel {
         self.reveal_cost_cached[slot]
     }
 
+    /// The weighted cost of one started echo that spent `tuner` and `exp`.
+    pub fn weighted_attempt_cost(&self, tuner: f64, exp: f64) -> f64 {
+        self.echo_acquisition_cost
+            + self.weight_echo * ECHO_COST
+            + self.weight_tuner * tuner
+            + self.weight_exp * exp
+    }
+
+    /// The variance of `weighted_attempt_cost` given the (co)variances of tuner and exp.
+    pub fn weighted_attempt_cost_variance(
+        &self,
+        tuner_variance: f64,
+        exp_variance: f64,
+        tuner_exp_covariance: f64,
+    ) -> f64 {
+        self.weight_tuner * self.weight_tuner * tuner_variance
+            + self.weight_exp * self.weight_exp * exp_variance
+            + 2.0 * self.weight_tuner * self.weight_exp * tuner_exp_covariance
+    }
+
     /// The additional tuner cost for an echo that is kept.
     pub fn success_additional_tuner_cost(&self) -> f64 {
-        TUNER_COST * TUNER_REFUND_RATIO * (NUM_ECHO_SLOTS as f64)
+        self.success_additional_tuner_cost_at(NUM_ECHO_SLOTS)
     }
 
     /// The additional exp cost for an echo that is kept.
     pub fn success_additional_exp_cost(&self) -> f64 {
-        self.exp_refund_ratio * EXP_COST_BY_LEVEL[NUM_ECHO_SLOTS - 1]
+        self.success_additional_exp_cost_at(NUM_ECHO_SLOTS)
     }
 
     /// The weighted additional cost for an echo that is kept.
     pub fn weighted_success_additional_cost(&self) -> f64 {
-        self.weight_tuner * self.success_additional_tuner_cost()
-            + self.weight_exp * self.success_additional_exp_cost()
+        self.weighted_success_additional_cost_at(NUM_ECHO_SLOTS)
+    }
+
+    /// The additional tuner cost for an echo that is kept with `num_slots` revealed.
+    pub fn success_additional_tuner_cost_at(&self, num_slots: usize) -> f64 {
+        TUNER_COST * TUNER_REFUND_RATIO * (num_slots as f64)
+    }
+
+    /// The additional exp cost for an echo that is kept with `num_slots` revealed.
+    ///
+    /// Must ensure `num_slots` is in 1..=5
+    pub fn success_additional_exp_cost_at(&self, num_slots: usize) -> f64 {
+        self.exp_refund_ratio * EXP_COST_BY_LEVEL[num_slots - 1]
+    }
+
+    /// The weighted additional cost for an echo that is kept with `num_slots` revealed.
+    pub fn weighted_success_additional_cost_at(&self, num_slots: usize) -> f64 {
+        self.weight_tuner * self.success_additional_tuner_cost_at(num_slots)
+            + self.weight_exp * self.success_additional_exp_cost_at(num_slots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_cost_weights_accept_the_dkq_alias() {
+        let legacy: LegacyCostWeights =
+            serde_json::from_str(r#"{"wEcho": 1.0, "wDkq": 2.0, "wExp": 3.0}"#).unwrap();
+        let cost_model = CostModel::from_legacy_weights(legacy, 0.5).unwrap();
+        assert_eq!(cost_model.weights(), (1.0, 2.0, 3.0));
+        assert_eq!(cost_model.exp_refund_ratio(), 0.5);
+
+        let current: LegacyCostWeights = serde_json::from_str(r#"{"wTuner": 2.0}"#).unwrap();
+        let cost_model = CostModel::from_legacy_weights(current, 0.5).unwrap();
+        assert_eq!(cost_model.weights(), (0.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn exp_refund_ratio_error_names_the_maximum() {
+        let Err(error) = CostModel::new(0.0, 1.0, 0.0, 0.9) else {
+            panic!("a refund ratio above the maximum should be rejected");
+        };
+        assert_eq!(
+            error.to_string(),
+            "exp refund ratio must be in [0, 0.75], got 0.9"
+        );
     }
 }
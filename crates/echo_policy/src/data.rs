@@ -3,8 +3,18 @@ pub const NUM_ECHO_SLOTS: usize = 5;
 
 pub type Histogram = &'static [(u16, u32)];
 
+/// The count-weighted mean buff value of a histogram.
+pub fn histogram_mean_value(histogram: Histogram) -> f64 {
+    let mut total_counts: f64 = 0.0;
+    let mut total_values: f64 = 0.0;
+    for &(value, count) in histogram.iter() {
+        total_counts += count as f64;
+        total_values += value as f64 * count as f64;
+    }
+    total_values / total_counts
+}
+
 pub struct BuffData {
-    #[allow(dead_code)]
     pub name: &'static str,
     pub histogram: Histogram,
 }
@@ -128,6 +138,46 @@ pub const BUFF_MAX_VALUES: [u16; NUM_BUFFS] = [
     105, 210, 116, 147, 116, 60, 70, 580, 124, 116, 116, 116, 116,
 ];
 
+/// The English name of the buff at `index`.
+pub fn buff_name(index: usize) -> Option<&'static str> {
+    BUFF_TYPES.get(index).map(|buff| buff.name)
+}
+
+/// The English names of all buffs, in buff index order.
+pub fn buff_names() -> [&'static str; NUM_BUFFS] {
+    std::array::from_fn(|index| BUFF_TYPES[index].name)
+}
+
+/// The values a roll of `buff_index` can take, in ascending order.
+///
+/// Empty if `buff_index` is out of range.
+pub fn buff_value_options(buff_index: usize) -> Vec<u16> {
+    let Some(buff) = BUFF_TYPES.get(buff_index) else {
+        return Vec::new();
+    };
+    let mut values: Vec<u16> = buff.histogram.iter().map(|&(value, _)| value).collect();
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+/// The probability that a single roll of `buff_index` is at least `value`.
+///
+/// `1.0 - prob_roll_at_least(buff_index, value)` is the probability of rolling below `value`.
+/// Panics if `buff_index` is out of range.
+pub fn prob_roll_at_least(buff_index: usize, value: u16) -> f64 {
+    let histogram = BUFF_TYPES[buff_index].histogram;
+    let mut total_counts: u64 = 0;
+    let mut hit_counts: u64 = 0;
+    for &(roll_value, count) in histogram.iter() {
+        total_counts += count as u64;
+        if roll_value >= value {
+            hit_counts += count as u64;
+        }
+    }
+    hit_counts as f64 / total_counts as f64
+}
+
 pub const BUFF_FIXED_VALUE_INDEX: [usize; 3] = [5, 6, 7];
 
 pub static BUFF_TYPES: [BuffData; NUM_BUFFS] = [
@@ -5,8 +5,75 @@ mod reroll_policy;
 mod scoring;
 mod upgrade_policy;
 
-pub use cost::{CostModel, CostModelError};
-pub use mask::{bits_to_mask, mask_to_bits};
-pub use reroll_policy::{LockChoice, RerollPolicySolver, RerollPolicySolverError};
-pub use scoring::{FixedScorer, InternalScorer, LinearScorer, SCORE_MULTIPLIER, ScorerError};
-pub use upgrade_policy::{ExpectedUpgradeCost, UpgradePolicySolver, UpgradePolicySolverError};
+pub use cost::{CostModel, CostModelError, LegacyCostWeights};
+pub use data::{buff_name, buff_names, buff_value_options, prob_roll_at_least};
+pub use mask::{EchoMask, FullMask, PartialMask, bits_to_mask, mask_to_bits, partial_mask_at};
+pub use reroll_policy::{
+    InventoryEntry, LockChoice, LockChoiceView, MaskStatus, RerollOutcome, RerollPolicySolver,
+    RerollPolicySolverBuilder, RerollPolicySolverError, combined_expected_cost,
+    lock_slot_indices_from_mask,
+};
+pub use scoring::{
+    DisplayScore, EchoParseError, FixedScorer, InternalScorer, LinearScorer, RawScore,
+    SCORE_MULTIPLIER, ScorerError, min_weights_for_target, parse_echo,
+};
+pub use upgrade_policy::{
+    CacheStats, ConfigFingerprint, CostBreakdown, DEFAULT_PMF_SUM_TOL, ExpectedUpgradeCost,
+    FarmingEstimate, MAX_FIRST_OF_TARGETS, ScorerComparison, Timings, UpgradePolicySolver,
+    UpgradePolicySolverError, WorkEstimate,
+};
+
+/// A single error type covering every fallible API of the crate.
+#[derive(Debug)]
+pub enum EchoPolicyError {
+    CostModel(CostModelError),
+    Scorer(ScorerError),
+    UpgradePolicySolver(UpgradePolicySolverError),
+    RerollPolicySolver(RerollPolicySolverError),
+}
+
+impl std::fmt::Display for EchoPolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CostModel(err) => write!(f, "cost model error: {err}"),
+            Self::Scorer(err) => write!(f, "scorer error: {err}"),
+            Self::UpgradePolicySolver(err) => write!(f, "upgrade policy solver error: {err}"),
+            Self::RerollPolicySolver(err) => write!(f, "reroll policy solver error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for EchoPolicyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::CostModel(err) => Some(err),
+            Self::Scorer(err) => Some(err),
+            Self::UpgradePolicySolver(err) => Some(err),
+            Self::RerollPolicySolver(err) => Some(err),
+        }
+    }
+}
+
+impl From<CostModelError> for EchoPolicyError {
+    fn from(err: CostModelError) -> Self {
+        Self::CostModel(err)
+    }
+}
+
+impl From<ScorerError> for EchoPolicyError {
+    fn from(err: ScorerError) -> Self {
+        Self::Scorer(err)
+    }
+}
+
+impl From<UpgradePolicySolverError> for EchoPolicyError {
+    fn from(err: UpgradePolicySolverError) -> Self {
+        Self::UpgradePolicySolver(err)
+    }
+}
+
+impl From<RerollPolicySolverError> for EchoPolicyError {
+    fn from(err: RerollPolicySolverError) -> Self {
+        Self::RerollPolicySolver(err)
+    }
+}
@@ -146,18 +146,93 @@ const fn build_full_mask_to_index() -> [usize; FULL_MASK_SPACE + 1] {
     map
 }
 
-/// It does not check whether `mask` is a valid partial mask.
+/// It does not check whether `mask` is a valid partial mask (except in debug builds).
 #[inline(always)]
 pub fn partial_mask_to_index(mask: u16) -> usize {
+    debug_assert!(
+        is_valid_external_partial_mask(mask),
+        "partial_mask_to_index: invalid partial mask {mask:#015b}"
+    );
     PARTIAL_MASK_TO_INDEX[mask as usize]
 }
 
-/// It does not check whether `mask` is a valid full mask.
+/// It does not check whether `mask` is a valid full mask (except in debug builds).
 #[inline(always)]
 pub fn full_mask_to_index(mask: u16) -> usize {
+    debug_assert!(
+        is_valid_external_full_mask(mask),
+        "full_mask_to_index: invalid full mask {mask:#015b}"
+    );
     FULL_MASK_TO_INDEX[mask as usize]
 }
 
+/// The partial mask stored at `index` in the solver's partial mask layout.
+pub fn partial_mask_at(index: usize) -> Option<u16> {
+    PARTIAL_MASKS.get(index).copied()
+}
+
+/// A mask validated to have 0 to 4 revealed buffs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialMask(u16);
+
+impl PartialMask {
+    pub fn new(mask: u16) -> Option<Self> {
+        is_valid_external_partial_mask(mask).then_some(Self(mask))
+    }
+
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+}
+
+/// A mask validated to have all 5 buffs revealed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FullMask(u16);
+
+impl FullMask {
+    pub fn new(mask: u16) -> Option<Self> {
+        is_valid_external_full_mask(mask).then_some(Self(mask))
+    }
+
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+}
+
+/// A validated mask of either kind, for APIs that treat partial and full masks differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EchoMask {
+    Partial(PartialMask),
+    Full(FullMask),
+}
+
+impl EchoMask {
+    pub fn new(mask: u16) -> Option<Self> {
+        PartialMask::new(mask)
+            .map(Self::Partial)
+            .or_else(|| FullMask::new(mask).map(Self::Full))
+    }
+
+    pub fn bits(self) -> u16 {
+        match self {
+            Self::Partial(mask) => mask.bits(),
+            Self::Full(mask) => mask.bits(),
+        }
+    }
+}
+
+impl From<PartialMask> for EchoMask {
+    fn from(mask: PartialMask) -> Self {
+        Self::Partial(mask)
+    }
+}
+
+impl From<FullMask> for EchoMask {
+    fn from(mask: FullMask) -> Self {
+        Self::Full(mask)
+    }
+}
+
 pub fn bits_to_mask(bits: &[u8]) -> u16 {
     let mut mask: u16 = 0;
     for (index, &bit) in bits.iter().enumerate().take(NUM_BUFFS) {
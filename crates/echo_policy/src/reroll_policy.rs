@@ -1,23 +1,29 @@
+#[cfg(feature = "simulate")]
+use rand::Rng;
 use rayon::prelude::*;
+use serde::Serialize;
 
-use crate::data::{NUM_BUFFS, NUM_ECHO_SLOTS};
+use crate::data::{BUFF_MAX_VALUES, BUFF_TYPES, NUM_BUFFS, NUM_ECHO_SLOTS, histogram_mean_value};
 use crate::mask::{
     FULL_MASK_SPACE, FULL_MASKS, NUM_FULL_MASKS, calculate_num_filled_slots, full_mask_to_index,
-    is_valid_external_full_mask,
+    is_valid_external_full_mask, is_valid_external_partial_mask, mask_to_bits,
+};
+use crate::{
+    CostModel, FixedScorer, InternalScorer, LinearScorer, SCORE_MULTIPLIER, ScorerError,
+    UpgradePolicySolver,
 };
-use crate::{FixedScorer, InternalScorer, ScorerError};
 
 const MAX_LOCK_SIZE: usize = NUM_ECHO_SLOTS - 1;
 
-#[inline(always)]
-fn lock_cost(k: usize) -> f64 {
-    match k {
-        0..=2 => 1.0,
-        3 => 2.0,
-        4 => 3.0,
-        _ => f64::INFINITY,
-    }
-}
+const POLICY_BIN_MAGIC: &[u8; 4] = b"ERRP";
+const POLICY_BIN_VERSION: u8 = 2;
+// Stored in place of `None` in the best lock section.
+const POLICY_BIN_NO_LOCK: u16 = u16::MAX;
+
+// The cost of one reroll by the number of locked slots.
+const DEFAULT_LOCK_COSTS: [f64; NUM_ECHO_SLOTS] = [1.0, 1.0, 1.0, 2.0, 3.0];
+const DEFAULT_TOLERANCE: f64 = 1e-4;
+const DEFAULT_MAX_ITER: usize = 200;
 
 #[derive(Debug)]
 pub enum RerollPolicySolverError {
@@ -26,11 +32,74 @@ pub enum RerollPolicySolverError {
     AllWeightsZero,
     TopWeightsTooLarge { sum: u32 },
     InvalidMask { mask: u16 },
+    InvalidLockMask { mask: u16, lock_mask: u16 },
+    BuffNotInMask { mask: u16, buff_index: usize },
     InvalidTolerance { tolerance: f64 },
+    InvalidEpsilon { epsilon: f64 },
+    InvalidAcceptThreshold { threshold: f64 },
+    InvalidMainStatScore { score: f64 },
+    InvalidLockCost { lock_size: usize, cost: f64 },
     TargetScoreImpossible { target_score: u16, max_score: u16 },
+    InvalidTargetBand { low: f64, high: f64 },
+    InvalidBucketCount { buckets: usize },
     TargetNotSet,
+    InvalidPolicyBin { reason: &'static str },
+}
+
+impl std::fmt::Display for RerollPolicySolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PolicyNotDerived => write!(f, "policy has not been derived"),
+            Self::FailedtoConvergeWithinMaxIter => {
+                write!(
+                    f,
+                    "failed to converge within the maximum number of iterations"
+                )
+            }
+            Self::AllWeightsZero => write!(f, "at least one buff weight must be positive"),
+            Self::TopWeightsTooLarge { sum } => {
+                write!(f, "sum of the top fixed weights is too large: {sum}")
+            }
+            Self::InvalidMask { mask } => write!(f, "invalid mask {mask:#06x}"),
+            Self::InvalidLockMask { mask, lock_mask } => {
+                write!(
+                    f,
+                    "lock mask {lock_mask:#06x} is not valid for mask {mask:#06x}"
+                )
+            }
+            Self::BuffNotInMask { mask, buff_index } => {
+                write!(f, "buff {buff_index} is not in mask {mask:#06x}")
+            }
+            Self::InvalidTolerance { tolerance } => {
+                write!(f, "tolerance must be a positive number, got {tolerance}")
+            }
+            Self::InvalidEpsilon { epsilon } => write!(f, "invalid epsilon {epsilon}"),
+            Self::InvalidAcceptThreshold { threshold } => {
+                write!(f, "invalid accept threshold {threshold}")
+            }
+            Self::InvalidMainStatScore { score } => write!(f, "invalid main stat score {score}"),
+            Self::InvalidLockCost { lock_size, cost } => {
+                write!(f, "invalid cost {cost} for locking {lock_size} buffs")
+            }
+            Self::TargetScoreImpossible {
+                target_score,
+                max_score,
+            } => write!(
+                f,
+                "target score {target_score} exceeds the maximum possible score {max_score}"
+            ),
+            Self::InvalidTargetBand { low, high } => {
+                write!(f, "invalid target band [{low}, {high}]")
+            }
+            Self::InvalidBucketCount { buckets } => write!(f, "invalid bucket count {buckets}"),
+            Self::TargetNotSet => write!(f, "target score has not been set"),
+            Self::InvalidPolicyBin { reason } => write!(f, "invalid policy file: {reason}"),
+        }
+    }
 }
 
+impl std::error::Error for RerollPolicySolverError {}
+
 impl From<ScorerError> for RerollPolicySolverError {
     fn from(err: ScorerError) -> Self {
         match err {
@@ -51,13 +120,94 @@ pub struct LockChoice {
     pub success_probability: f64,
 }
 
+/// A `LockChoice` laid out for display, serialized in camelCase.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockChoiceView {
+    /// `mask_to_bits` of the lock mask.
+    pub lock_mask_bits: Vec<u8>,
+    /// The 1-based slots of the baseline echo that are locked.
+    pub lock_slot_indices: Vec<usize>,
+    pub expected_cost: f64,
+    pub regret: f64,
+    pub success_probability: f64,
+}
+
+/// The 1-based positions in `baseline_names` (`buff_name`s) of the buffs in `lock_mask`.
+/// Unknown names are never locked.
+pub fn lock_slot_indices_from_mask(lock_mask: u16, baseline_names: &[&str]) -> Vec<usize> {
+    let mut slots = Vec::new();
+    for (slot_index, &name) in baseline_names.iter().enumerate() {
+        if let Some(buff_index) = BUFF_TYPES.iter().position(|buff| buff.name == name)
+            && (lock_mask & (1u16 << buff_index)) != 0
+        {
+            slots.push(slot_index + 1);
+        }
+    }
+    slots
+}
+
+/// The weighted cost to reroll `start_mask` until it reaches the reroll target and then
+/// level the resulting echo to all five slots.
+///
+/// Both parts use the upgrade solver's `CostModel`: reroll lock costs are scaled by its
+/// `reroll_unit_cost`, and leveling pays the full tuner and exp of a kept echo.
+pub fn combined_expected_cost(
+    upgrade: &UpgradePolicySolver,
+    reroll: &RerollPolicySolver,
+    start_mask: u16,
+) -> Result<f64, RerollPolicySolverError> {
+    let cost_model = upgrade.cost_model();
+    let reroll_cost = reroll.expected_lock_cost(start_mask)? * cost_model.reroll_unit_cost();
+    Ok(reroll_cost + weighted_leveling_cost(cost_model))
+}
+
+fn weighted_leveling_cost(cost_model: &CostModel) -> f64 {
+    let (_, weight_tuner, weight_exp) = cost_model.weights();
+    let (tuner, exp) = cost_model.full_upgrade_cost(0);
+    weight_tuner * tuner + weight_exp * exp + cost_model.weighted_success_additional_cost()
+}
+
+#[derive(Clone)]
+pub struct InventoryEntry {
+    pub mask: u16,
+    pub success: bool,
+    pub expected_cost: f64,
+    pub lock_choices: Vec<LockChoice>,
+}
+
+/// Where a full mask stands under the derived policy, see `classify_masks`.
+#[derive(Debug, Clone, Copy)]
+pub enum MaskStatus {
+    Success,
+    Pending {
+        expected_cost: f64,
+        /// The probability that one reroll with the best lock reaches the target.
+        best_lock_success_probability: f64,
+    },
+}
+
+#[derive(Clone)]
+pub struct RerollOutcome {
+    pub final_mask: u16,
+    pub rerolls: usize,
+    pub total_cost: f64,
+    /// The number of locked slots of each reroll, in order.
+    pub lock_sizes: Vec<usize>,
+}
+
 pub struct RerollPolicySolver {
+    scorer: FixedScorer,
     scores: [u16; NUM_FULL_MASKS],
     max_score: u16,
+    main_stat_score: f64,
+    lock_costs: [f64; NUM_ECHO_SLOTS],
     lock_sets: Vec<Vec<u16>>,
     transitions: Vec<Vec<usize>>,
 
     target_score: Option<u16>,
+    // `(low, high)` set by `set_target_band`, with `target_score` holding `low` rounded up.
+    target_band: Option<(f64, f64)>,
     success: [bool; NUM_FULL_MASKS],
     success_count: usize,
     policy_derived: bool,
@@ -65,6 +215,70 @@ pub struct RerollPolicySolver {
     action_cache: Vec<Vec<LockChoice>>,
     best_lock_cache: [Option<u16>; NUM_FULL_MASKS],
     lock_success_probability_cache: Vec<f64>,
+    // Success probability within the rerolls given to `derive_finite_horizon`.
+    finite_horizon_success: Option<[f64; NUM_FULL_MASKS]>,
+    parallel_threshold: usize,
+    // Success count of the target `dp` last converged for, kept across `set_target`.
+    converged_success_count: Option<usize>,
+    // Sweeps the last value iteration took to converge.
+    value_iterations: Option<usize>,
+}
+
+/// Chains `RerollPolicySolver::new`, `set_lock_costs`, `set_target` and `derive_policy`.
+pub struct RerollPolicySolverBuilder {
+    weights: [u16; NUM_BUFFS],
+    target_score: Option<u16>,
+    tol: f64,
+    max_iter: usize,
+    lock_costs: [f64; NUM_ECHO_SLOTS],
+}
+
+impl RerollPolicySolverBuilder {
+    pub fn new(weights: [u16; NUM_BUFFS]) -> Self {
+        Self {
+            weights,
+            target_score: None,
+            tol: DEFAULT_TOLERANCE,
+            max_iter: DEFAULT_MAX_ITER,
+            lock_costs: DEFAULT_LOCK_COSTS,
+        }
+    }
+
+    /// Required, `build` fails with `TargetNotSet` without it.
+    pub fn target(mut self, target_score: u16) -> Self {
+        self.target_score = Some(target_score);
+        self
+    }
+
+    /// Value iteration tolerance, 1e-4 by default.
+    pub fn tol(mut self, tol: f64) -> Self {
+        self.tol = tol;
+        self
+    }
+
+    /// Value iteration limit, 200 by default.
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// See `RerollPolicySolver::set_lock_costs`.
+    pub fn lock_costs(mut self, costs: [f64; NUM_ECHO_SLOTS]) -> Self {
+        self.lock_costs = costs;
+        self
+    }
+
+    /// Create the solver with its policy derived, or return the first error.
+    pub fn build(self) -> Result<RerollPolicySolver, RerollPolicySolverError> {
+        let target_score = self
+            .target_score
+            .ok_or(RerollPolicySolverError::TargetNotSet)?;
+        let mut solver = RerollPolicySolver::new(self.weights)?;
+        solver.set_lock_costs(self.lock_costs)?;
+        solver.set_target(target_score)?;
+        solver.derive_policy(self.tol, self.max_iter)?;
+        Ok(solver)
+    }
 }
 
 impl RerollPolicySolver {
@@ -72,12 +286,72 @@ impl RerollPolicySolver {
         self.target_score.is_some()
     }
 
+    /// The target in fixed score units, `None` before `set_target`.
+    pub fn target_score(&self) -> Option<u16> {
+        self.target_score
+    }
+
     pub fn is_policy_derived(&self) -> bool {
         self.policy_derived
     }
+
+    /// The value iteration sweeps the last `derive_policy` or `derive_policy_warm` took to
+    /// converge, `None` if it failed or the policy was loaded with `load_policy_bin`.
+    pub fn value_iterations(&self) -> Option<usize> {
+        self.value_iterations
+    }
+
+    /// Use a serial sweep in `derive_policy` when fewer than `min_masks` masks are not
+    /// successful. The default of 0 always sweeps in parallel.
+    pub fn set_parallel_threshold(&mut self, min_masks: usize) {
+        self.parallel_threshold = min_masks;
+    }
 }
 
 impl RerollPolicySolver {
+    /// The display score of the buffs of `mask` when every buff rolls its histogram mean
+    /// value, main stat excluded.
+    ///
+    /// A buff scores its weight at its maximum value and proportionally less below it.
+    /// Success is decided on the value-independent score, this is only for display.
+    pub fn expected_score_of_mask(&self, mask: u16) -> Result<f64, RerollPolicySolverError> {
+        if !is_valid_external_full_mask(mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask });
+        }
+
+        let weights = self
+            .scorer
+            .buff_weights()
+            .expect("fixed scorers are defined by weights");
+        let scorer = LinearScorer::new(weights, 0.0, f64::from(self.max_score) / SCORE_MULTIPLIER)
+            .expect("weights accepted by the fixed scorer should be valid");
+        let mut sum: f64 = 0.0;
+        for (buff_index, buff) in BUFF_TYPES.iter().enumerate() {
+            if (mask & (1u16 << buff_index)) == 0 {
+                continue;
+            }
+            // The scorer is linear in the value, so the mean value gets the mean score.
+            let max_value = BUFF_MAX_VALUES[buff_index];
+            let max_value_score = scorer
+                .buff_score_display(buff_index, max_value)
+                .expect("maximum values should always be valid");
+            sum += max_value_score * histogram_mean_value(buff.histogram) / max_value as f64;
+        }
+        Ok(sum)
+    }
+
+    /// `expected_score_of_mask(candidate_mask) - expected_score_of_mask(baseline_mask)`, the
+    /// display score gained (or lost, if negative) by accepting the candidate.
+    pub fn expected_score_gain(
+        &self,
+        baseline_mask: u16,
+        candidate_mask: u16,
+    ) -> Result<f64, RerollPolicySolverError> {
+        let baseline_score = self.expected_score_of_mask(baseline_mask)?;
+        let candidate_score = self.expected_score_of_mask(candidate_mask)?;
+        Ok(candidate_score - baseline_score)
+    }
+
     pub fn best_lock_choices(&self, mask: u16) -> Result<Option<u16>, RerollPolicySolverError> {
         if !self.is_policy_derived() {
             return Err(RerollPolicySolverError::PolicyNotDerived);
@@ -94,6 +368,87 @@ impl RerollPolicySolver {
         Ok(self.best_lock_cache[index])
     }
 
+    /// The smallest full mask reachable from `mask` by swapping buffs of equal weight.
+    ///
+    /// `scores` sums the per-buff weights of each mask, so masks differing only by such
+    /// swaps share their score, and so does every mask locking or rerolling into them,
+    /// which gives them identical reroll dynamics. Each buff of `mask` is replaced, in
+    /// index order, by the lowest-indexed unused buff of the same weight. Invalid masks
+    /// are returned unchanged.
+    pub fn canonical_mask(&self, mask: u16) -> u16 {
+        if !is_valid_external_full_mask(mask) {
+            return mask;
+        }
+        let buff_score = |buff_index: usize| {
+            self.scorer
+                .buff_score_internal(buff_index, 0)
+                .expect("built in buff entries should always be valid")
+        };
+
+        let mut canonical: u16 = 0;
+        for buff_index in 0..NUM_BUFFS {
+            if mask & (1u16 << buff_index) == 0 {
+                continue;
+            }
+            let score = buff_score(buff_index);
+            let replacement = (0..NUM_BUFFS)
+                .find(|&candidate| {
+                    canonical & (1u16 << candidate) == 0 && buff_score(candidate) == score
+                })
+                .expect("a buff always matches its own score");
+            canonical |= 1u16 << replacement;
+        }
+        canonical
+    }
+
+    /// Whether the best lock choice for `mask` keeps `buff_index`. Always false for a
+    /// mask that already succeeds, since nothing is rerolled.
+    pub fn should_lock_slot(
+        &self,
+        mask: u16,
+        buff_index: usize,
+    ) -> Result<bool, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        if !is_valid_external_full_mask(mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask });
+        }
+        if buff_index >= NUM_BUFFS || mask & (1u16 << buff_index) == 0 {
+            return Err(RerollPolicySolverError::BuffNotInMask { mask, buff_index });
+        }
+
+        let index = full_mask_to_index(mask);
+        if self.success[index] {
+            return Ok(false);
+        }
+        Ok(self.best_lock_cache[index]
+            .is_some_and(|lock_mask| lock_mask & (1u16 << buff_index) != 0))
+    }
+
+    /// All lock choices whose regret is at most `epsilon`, best first.
+    pub fn best_lock_choices_within(
+        &self,
+        mask: u16,
+        epsilon: f64,
+    ) -> Result<Vec<LockChoice>, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        if !is_valid_external_full_mask(mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask });
+        }
+        if !epsilon.is_finite() || epsilon < 0.0 {
+            return Err(RerollPolicySolverError::InvalidEpsilon { epsilon });
+        }
+
+        Ok(self.action_cache[full_mask_to_index(mask)]
+            .iter()
+            .take_while(|choice| choice.regret <= epsilon)
+            .cloned()
+            .collect())
+    }
+
     pub fn lock_choices(
         &self,
         mask: u16,
@@ -116,6 +471,115 @@ impl RerollPolicySolver {
         Ok(choices[..keep].to_vec())
     }
 
+    /// `lock_choices` as `LockChoiceView`s, with slots numbered by `baseline_names`,
+    /// the `buff_name`s of the echo in slot order.
+    pub fn lock_choices_view(
+        &self,
+        mask: u16,
+        top_k: usize,
+        baseline_names: &[&str],
+    ) -> Result<Vec<LockChoiceView>, RerollPolicySolverError> {
+        Ok(self
+            .lock_choices(mask, top_k)?
+            .into_iter()
+            .map(|choice| LockChoiceView {
+                lock_mask_bits: mask_to_bits(choice.lock_mask).to_vec(),
+                lock_slot_indices: lock_slot_indices_from_mask(choice.lock_mask, baseline_names),
+                expected_cost: choice.expected_cost,
+                regret: choice.regret,
+                success_probability: choice.success_probability,
+            })
+            .collect())
+    }
+
+    /// Play out the optimal policy from `start` with random rerolls until the target is reached.
+    ///
+    /// Each reroll uses the best lock, and the rerolled echo is kept iff `should_accept` would.
+    /// Requires the `simulate` feature.
+    #[cfg(feature = "simulate")]
+    pub fn simulate_reroll<R: Rng>(
+        &self,
+        start: u16,
+        rng: &mut R,
+    ) -> Result<RerollOutcome, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        if !is_valid_external_full_mask(start) {
+            return Err(RerollPolicySolverError::InvalidMask { mask: start });
+        }
+
+        let mut index = full_mask_to_index(start);
+        let mut total_cost: f64 = 0.0;
+        let mut lock_sizes = Vec::new();
+        while !self.success[index] {
+            let lock_mask = self.best_lock_cache[index]
+                .expect("a derived policy has a best lock for every non-success mask");
+            let k = calculate_num_filled_slots(lock_mask);
+            let candidates = &self.transitions[lock_mask as usize];
+            let candidate_index = candidates[rng.gen_range(0..candidates.len())];
+
+            total_cost += self.lock_cost(k);
+            lock_sizes.push(k);
+            if self.dp[candidate_index] <= self.dp[index] {
+                index = candidate_index;
+            }
+        }
+
+        Ok(RerollOutcome {
+            final_mask: FULL_MASKS[index],
+            rerolls: lock_sizes.len(),
+            total_cost,
+            lock_sizes,
+        })
+    }
+
+    /// Evaluate every echo of an inventory, cheapest to finish first.
+    ///
+    /// `top_k` is passed to `lock_choices` for each echo (0 keeps all choices).
+    pub fn rank_inventory(
+        &self,
+        masks: &[u16],
+        top_k: usize,
+    ) -> Result<Vec<InventoryEntry>, RerollPolicySolverError> {
+        let mut entries = masks
+            .par_iter()
+            .map(|&mask| {
+                let lock_choices = self.lock_choices(mask, top_k)?;
+                let index = full_mask_to_index(mask);
+                Ok(InventoryEntry {
+                    mask,
+                    success: self.success[index],
+                    expected_cost: self.dp[index],
+                    lock_choices,
+                })
+            })
+            .collect::<Result<Vec<_>, RerollPolicySolverError>>()?;
+        entries.sort_by(|lhs, rhs| lhs.expected_cost.total_cmp(&rhs.expected_cost));
+        Ok(entries)
+    }
+
+    /// The best lock choice of every mask in `masks` as `(index into masks, choice)`, cheapest
+    /// expected cost first, i.e. which reroll to do next across the whole inventory.
+    ///
+    /// Masks that already reach the target have no choice and are left out.
+    pub fn global_lock_ranking(
+        &self,
+        masks: &[u16],
+    ) -> Result<Vec<(usize, LockChoice)>, RerollPolicySolverError> {
+        let best_choices = masks
+            .par_iter()
+            .enumerate()
+            .map(|(mask_index, &mask)| {
+                let choice = self.lock_choices(mask, 1)?.into_iter().next();
+                Ok(choice.map(|choice| (mask_index, choice)))
+            })
+            .collect::<Result<Vec<_>, RerollPolicySolverError>>()?;
+        let mut ranking: Vec<(usize, LockChoice)> = best_choices.into_iter().flatten().collect();
+        ranking.sort_by(|lhs, rhs| lhs.1.expected_cost.total_cmp(&rhs.1.expected_cost));
+        Ok(ranking)
+    }
+
     pub fn expected_lock_cost(&self, mask: u16) -> Result<f64, RerollPolicySolverError> {
         if !self.is_policy_derived() {
             return Err(RerollPolicySolverError::PolicyNotDerived);
@@ -126,6 +590,123 @@ impl RerollPolicySolver {
         Ok(self.dp[full_mask_to_index(mask)])
     }
 
+    /// The expected cost from `mask` when every reroll locks nothing.
+    ///
+    /// Each reroll is then a fresh uniform full mask, so the cost is geometric in
+    /// `success_fraction`. Compare with `expected_lock_cost` to see what locking saves.
+    /// Only needs the target, not a derived policy.
+    pub fn expected_cost_no_lock(&self, mask: u16) -> Result<f64, RerollPolicySolverError> {
+        if !is_valid_external_full_mask(mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask });
+        }
+        let success_fraction = self.success_fraction()?;
+        if self.success[full_mask_to_index(mask)] {
+            return Ok(0.0);
+        }
+        Ok(self.lock_cost(0) / success_fraction)
+    }
+
+    /// The expected cost from a uniformly random first echo, i.e. the mean dp over all
+    /// full masks (successes count as 0).
+    pub fn expected_cost_from_random(&self) -> Result<f64, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        Ok(self.dp.iter().sum::<f64>() / NUM_FULL_MASKS as f64)
+    }
+
+    /// A histogram of the expected cost over all full masks that are not yet a success.
+    ///
+    /// Returns `(lower edge, mask count)` for `buckets` equal-width bins spanning the lowest
+    /// to the highest cost, with the highest cost in the last bin. Empty if every mask
+    /// already succeeds.
+    pub fn cost_distribution(
+        &self,
+        buckets: usize,
+    ) -> Result<Vec<(f64, usize)>, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        if buckets == 0 {
+            return Err(RerollPolicySolverError::InvalidBucketCount { buckets });
+        }
+
+        let costs: Vec<f64> = self
+            .dp
+            .iter()
+            .zip(self.success.iter())
+            .filter(|&(_, &success)| !success)
+            .map(|(&dp, _)| dp)
+            .collect();
+        if costs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let min_cost = costs.iter().copied().fold(f64::INFINITY, f64::min);
+        let max_cost = costs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let width = (max_cost - min_cost) / buckets as f64;
+
+        let mut histogram: Vec<(f64, usize)> = (0..buckets)
+            .map(|bucket| (min_cost + width * bucket as f64, 0))
+            .collect();
+        for &cost in costs.iter() {
+            let bucket = if width > 0.0 {
+                (((cost - min_cost) / width) as usize).min(buckets - 1)
+            } else {
+                0
+            };
+            histogram[bucket].1 += 1;
+        }
+        Ok(histogram)
+    }
+
+    /// The expected cost for an echo with fewer than 5 revealed buffs.
+    ///
+    /// Unrevealed slots are drawn uniformly, so this is the mean dp over all full masks
+    /// containing `partial_mask`.
+    pub fn expected_cost_partial(&self, partial_mask: u16) -> Result<f64, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        if !is_valid_external_partial_mask(partial_mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask: partial_mask });
+        }
+
+        let mut total: f64 = 0.0;
+        let mut count: usize = 0;
+        for (index, &mask) in FULL_MASKS.iter().enumerate() {
+            if (mask & partial_mask) == partial_mask {
+                total += self.dp[index];
+                count += 1;
+            }
+        }
+        Ok(total / count as f64)
+    }
+
+    /// The best expected cost among lock choices of each lock size (0 to 4 locked slots).
+    ///
+    /// All entries are `None` for a mask that already reaches the target.
+    pub fn cost_by_lock_count(
+        &self,
+        mask: u16,
+    ) -> Result<[Option<f64>; NUM_ECHO_SLOTS], RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        if !is_valid_external_full_mask(mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask });
+        }
+
+        let mut costs = [None; NUM_ECHO_SLOTS];
+        // Choices are sorted by expected cost, so the first hit per size is the best.
+        for choice in self.action_cache[full_mask_to_index(mask)].iter() {
+            let cost = &mut costs[calculate_num_filled_slots(choice.lock_mask)];
+            if cost.is_none() {
+                *cost = Some(choice.expected_cost);
+            }
+        }
+        Ok(costs)
+    }
+
     pub fn best_lock_success_probability(
         &self,
         mask: u16,
@@ -147,6 +728,128 @@ impl RerollPolicySolver {
             .map(|choice| choice.success_probability))
     }
 
+    /// The Shannon entropy in bits of the best lock size over all masks that are not yet a
+    /// success: 0 when one lock size is always best, up to log2(5) when all are equally common.
+    ///
+    /// Returns 0 if every mask already succeeds.
+    pub fn policy_entropy(&self) -> Result<f64, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+
+        let mut counts = [0usize; NUM_ECHO_SLOTS];
+        for lock_mask in self.best_lock_cache.iter().flatten() {
+            counts[calculate_num_filled_slots(*lock_mask)] += 1;
+        }
+        let total: usize = counts.iter().sum();
+        if total == 0 {
+            return Ok(0.0);
+        }
+        Ok(counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let probability = count as f64 / total as f64;
+                -probability * probability.log2()
+            })
+            .sum())
+    }
+
+    /// The `MaskStatus` of every mask in `masks`, in order, computed in parallel.
+    ///
+    /// All masks are validated first, so an invalid one fails the whole batch.
+    pub fn classify_masks(
+        &self,
+        masks: &[u16],
+    ) -> Result<Vec<MaskStatus>, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        if let Some(&mask) = masks
+            .iter()
+            .find(|&&mask| !is_valid_external_full_mask(mask))
+        {
+            return Err(RerollPolicySolverError::InvalidMask { mask });
+        }
+
+        Ok(masks
+            .par_iter()
+            .map(|&mask| {
+                let index = full_mask_to_index(mask);
+                if self.success[index] {
+                    return MaskStatus::Success;
+                }
+                MaskStatus::Pending {
+                    expected_cost: self.dp[index],
+                    best_lock_success_probability: self.action_cache[index]
+                        .first()
+                        .map_or(0.0, |choice| choice.success_probability),
+                }
+            })
+            .collect())
+    }
+
+    /// The probability that one reroll keeping `lock_mask` (at most 4 buffs) reaches the target.
+    ///
+    /// Unlike `lock_choices`, this takes any lock, not only those of a given baseline.
+    pub fn lock_success_probability(&self, lock_mask: u16) -> Result<f64, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        if !is_valid_external_partial_mask(lock_mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask: lock_mask });
+        }
+
+        if !self.transitions[lock_mask as usize].is_empty() {
+            return Ok(self.lock_success_probability_cache[lock_mask as usize]);
+        }
+        // Locks with zero-weight buffs are left out of the transitions, see `new`.
+        let (count, success_count) = FULL_MASKS
+            .iter()
+            .zip(self.success.iter())
+            .filter(|&(&mask, _)| (mask & lock_mask) == lock_mask)
+            .fold((0usize, 0usize), |(count, success_count), (_, &success)| {
+                (count + 1, success_count + usize::from(success))
+            });
+        Ok(success_count as f64 / count as f64)
+    }
+
+    /// How much one more reroll with the best lock raises the chance of reaching the target.
+    ///
+    /// A mask that already reaches the target has nothing to gain and returns 0.0.
+    pub fn one_step_success_gain(&self, mask: u16) -> Result<f64, RerollPolicySolverError> {
+        // A non-success mask has success indicator 0, so the gain is the one-step probability.
+        Ok(self.best_lock_success_probability(mask)?.unwrap_or(0.0))
+    }
+
+    /// Fraction of rerolls under `lock_mask` that the optimal policy would keep over `mask`.
+    ///
+    /// Unlike `LockChoice::success_probability`, a kept candidate need not reach the target.
+    pub fn lock_improvement_probability(
+        &self,
+        mask: u16,
+        lock_mask: u16,
+    ) -> Result<f64, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        if !is_valid_external_full_mask(mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask });
+        }
+        let index = full_mask_to_index(mask);
+        if !self.lock_sets[index].contains(&lock_mask) {
+            return Err(RerollPolicySolverError::InvalidLockMask { mask, lock_mask });
+        }
+
+        let baseline_dp = self.dp[index];
+        let candidates = &self.transitions[lock_mask as usize];
+        let improved = candidates
+            .iter()
+            .filter(|&&candidate_index| self.dp[candidate_index] <= baseline_dp)
+            .count();
+        Ok(improved as f64 / candidates.len() as f64)
+    }
+
     pub fn should_accept(
         &self,
         baseline_mask: u16,
@@ -169,6 +872,23 @@ impl RerollPolicySolver {
         let candidate_dp = self.dp[full_mask_to_index(candidate_mask)];
         Ok(candidate_dp <= baseline_dp)
     }
+
+    /// Accept the candidate if its best lock is at least as likely to succeed in one reroll.
+    ///
+    /// A mask that already reaches the target counts as success probability 1.0.
+    pub fn should_accept_by_success(
+        &self,
+        baseline_mask: u16,
+        candidate_mask: u16,
+    ) -> Result<bool, RerollPolicySolverError> {
+        let baseline_probability = self
+            .best_lock_success_probability(baseline_mask)?
+            .unwrap_or(1.0);
+        let candidate_probability = self
+            .best_lock_success_probability(candidate_mask)?
+            .unwrap_or(1.0);
+        Ok(candidate_probability >= baseline_probability)
+    }
 }
 
 impl RerollPolicySolver {
@@ -197,6 +917,8 @@ impl RerollPolicySolver {
             }
             scores[index] = sum;
 
+            // Zero-weight buffs are dead slots: they still occupy a slot of the mask and get
+            // a dp value, but locking one never helps, so they are left out of lock sets.
             let mut subsets = Vec::<u16>::with_capacity(1 << NUM_ECHO_SLOTS);
             let mut sub = mask;
             loop {
@@ -215,12 +937,16 @@ impl RerollPolicySolver {
         }
 
         Ok(Self {
+            scorer,
             scores,
             max_score,
+            main_stat_score: 0.0,
+            lock_costs: DEFAULT_LOCK_COSTS,
             lock_sets,
             transitions,
 
             target_score: None,
+            target_band: None,
             success: [false; NUM_FULL_MASKS],
             success_count: 0,
             policy_derived: false,
@@ -228,40 +954,153 @@ impl RerollPolicySolver {
             action_cache: vec![Vec::new(); NUM_FULL_MASKS],
             best_lock_cache: [None; NUM_FULL_MASKS],
             lock_success_probability_cache: vec![0.0; FULL_MASK_SPACE + 1],
+            finite_horizon_success: None,
+            parallel_threshold: 0,
+            converged_success_count: None,
+            value_iterations: None,
         })
     }
 
-    pub fn set_target(&mut self, target_score: u16) -> Result<(), RerollPolicySolverError> {
-        if target_score > self.max_score {
-            return Err(RerollPolicySolverError::TargetScoreImpossible {
-                target_score,
-                max_score: self.max_score,
+    /// Add a main stat score that every echo has on top of its buffs (0.0 by default).
+    ///
+    /// This lowers the score the buffs must reach. The policy must be derived again.
+    pub fn set_main_stat(&mut self, main_stat_score: f64) -> Result<(), RerollPolicySolverError> {
+        if !main_stat_score.is_finite() || main_stat_score < 0.0 {
+            return Err(RerollPolicySolverError::InvalidMainStatScore {
+                score: main_stat_score,
             });
         }
+        let previous = self.main_stat_score;
+        self.main_stat_score = main_stat_score;
+        let result = match (self.target_band, self.target_score) {
+            (Some((low, high)), _) => self.set_target_band(low, high),
+            (None, Some(target_score)) => self.set_target(target_score),
+            (None, None) => Ok(()),
+        };
+        if result.is_err() {
+            self.main_stat_score = previous;
+        }
+        result
+    }
+
+    /// Set the cost of one reroll with `k` locked slots to `costs[k]`.
+    ///
+    /// Defaults to 1, 1, 1, 2, 3. The policy must be derived again.
+    pub fn set_lock_costs(
+        &mut self,
+        costs: [f64; NUM_ECHO_SLOTS],
+    ) -> Result<(), RerollPolicySolverError> {
+        for (lock_size, &cost) in costs.iter().enumerate() {
+            if !cost.is_finite() || cost <= 0.0 {
+                return Err(RerollPolicySolverError::InvalidLockCost { lock_size, cost });
+            }
+        }
+        self.lock_costs = costs;
+        self.reset_policy_cache();
+        Ok(())
+    }
+
+    pub fn set_target(&mut self, target_score: u16) -> Result<(), RerollPolicySolverError> {
+        self.validate_target(target_score)?;
         self.target_score = Some(target_score);
+        self.target_band = None;
+        self.reset_policy_cache();
+
+        let (success, success_count) = self.success_where(|score| score >= f64::from(target_score));
+        self.success = success;
+        self.success_count = success_count;
+        Ok(())
+    }
+
+    /// Count an echo as a success only if `low <= score <= high`, main stat included,
+    /// so rerolling also moves away from overshooting echoes.
+    ///
+    /// `set_target(t)` is the band `[t, max]`. Banded policies can't be serialized.
+    pub fn set_target_band(&mut self, low: f64, high: f64) -> Result<(), RerollPolicySolverError> {
+        let max_score = f64::from(self.max_score) + self.main_stat_score;
+        if !low.is_finite() || !high.is_finite() || low > high || high > max_score {
+            return Err(RerollPolicySolverError::InvalidTargetBand { low, high });
+        }
+        let (success, success_count) = self.success_where(|score| low <= score && score <= high);
+        if success_count == 0 {
+            return Err(RerollPolicySolverError::InvalidTargetBand { low, high });
+        }
+
+        self.target_score = Some(low.ceil().clamp(0.0, u16::MAX as f64) as u16);
+        self.target_band = Some((low, high));
         self.reset_policy_cache();
+        self.success = success;
+        self.success_count = success_count;
+        Ok(())
+    }
 
-        self.success = [false; NUM_FULL_MASKS];
+    /// Which full masks succeed given their score plus the main stat, and how many.
+    fn success_where(&self, is_success: impl Fn(f64) -> bool) -> ([bool; NUM_FULL_MASKS], usize) {
+        let mut success = [false; NUM_FULL_MASKS];
         let mut success_count: usize = 0;
         for (index, &score) in self.scores.iter().enumerate() {
-            if score >= target_score {
-                self.success[index] = true;
+            if is_success(f64::from(score) + self.main_stat_score) {
+                success[index] = true;
                 success_count += 1;
             }
         }
-        self.success_count = success_count;
+        (success, success_count)
+    }
+
+    fn validate_target(&self, target_score: u16) -> Result<(), RerollPolicySolverError> {
+        let max_score = (f64::from(self.max_score) + self.main_stat_score)
+            .floor()
+            .min(u16::MAX as f64) as u16;
+        if target_score > max_score {
+            return Err(RerollPolicySolverError::TargetScoreImpossible {
+                target_score,
+                max_score,
+            });
+        }
         Ok(())
     }
+
+    /// The fraction of all full masks that already reach the current target.
+    pub fn success_fraction(&self) -> Result<f64, RerollPolicySolverError> {
+        if !self.is_target_set() {
+            return Err(RerollPolicySolverError::TargetNotSet);
+        }
+        Ok(self.success_count as f64 / NUM_FULL_MASKS as f64)
+    }
+
+    /// The full masks that reach the current target.
+    pub fn success_masks(&self) -> Result<Vec<u16>, RerollPolicySolverError> {
+        if !self.is_target_set() {
+            return Err(RerollPolicySolverError::TargetNotSet);
+        }
+        Ok(FULL_MASKS
+            .iter()
+            .zip(self.success.iter())
+            .filter(|&(_, &success)| success)
+            .map(|(&mask, _)| mask)
+            .collect())
+    }
 }
 
 impl RerollPolicySolver {
+    #[inline(always)]
+    fn lock_cost(&self, k: usize) -> f64 {
+        self.lock_costs[k]
+    }
+
     #[inline(always)]
     fn action_value(&self, baseline_dp: f64, lock_mask: u16) -> f64 {
+        self.action_value_in(&self.dp, baseline_dp, lock_mask)
+    }
+
+    /// `action_value` under `dp` instead of the solver's own.
+    #[inline(always)]
+    fn action_value_in(&self, dp: &[f64; NUM_FULL_MASKS], baseline_dp: f64, lock_mask: u16) -> f64 {
         let k = calculate_num_filled_slots(lock_mask);
         let candidates = &self.transitions[lock_mask as usize];
         let mut total: f64 = 0.0;
         for &candidate_index in candidates.iter() {
-            let candidate_dp = self.dp[candidate_index];
+            let candidate_dp = dp[candidate_index];
             total += if baseline_dp < candidate_dp {
                 baseline_dp
             } else {
@@ -269,7 +1108,26 @@ impl RerollPolicySolver {
             };
         }
         let expected = total / candidates.len() as f64;
-        lock_cost(k) + expected
+        self.lock_cost(k) + expected
+    }
+
+    /// Write the best action value for `index` into `value` and return the change in dp.
+    #[inline(always)]
+    fn bellman_update(&self, index: usize, value: &mut f64) -> f64 {
+        if self.success[index] {
+            return 0.0;
+        }
+
+        let baseline_dp = self.dp[index];
+        let mut best = f64::INFINITY;
+        for &lock_mask in self.lock_sets[index].iter() {
+            let dp = self.action_value(baseline_dp, lock_mask);
+            if dp < best {
+                best = dp;
+            }
+        }
+        *value = best;
+        (best - self.dp[index]).abs()
     }
 
     fn build_lock_success_probability_cache(&mut self) {
@@ -326,6 +1184,30 @@ impl RerollPolicySolver {
         self.best_lock_cache = best_lock_cache;
     }
 
+    /// The first lock `build_action_cache` would rank for each full mask under `dp` and
+    /// `success` instead of the solver's own.
+    fn best_locks_in(
+        &self,
+        dp: &[f64; NUM_FULL_MASKS],
+        success: &[bool; NUM_FULL_MASKS],
+    ) -> [Option<u16>; NUM_FULL_MASKS] {
+        let mut best_locks = [None; NUM_FULL_MASKS];
+        for (index, best_lock) in best_locks.iter_mut().enumerate() {
+            if success[index] {
+                continue;
+            }
+            let mut best: Option<(u16, f64)> = None;
+            for &lock_mask in self.lock_sets[index].iter() {
+                let value = self.action_value_in(dp, dp[index], lock_mask);
+                if best.is_none_or(|(_, best_value)| value.total_cmp(&best_value).is_lt()) {
+                    best = Some((lock_mask, value));
+                }
+            }
+            *best_lock = best.map(|(lock_mask, _)| lock_mask);
+        }
+        best_locks
+    }
+
     fn reset_policy_cache(&mut self) {
         self.policy_derived = false;
         self.best_lock_cache = [None; NUM_FULL_MASKS];
@@ -333,6 +1215,7 @@ impl RerollPolicySolver {
             choices.clear();
         }
         self.lock_success_probability_cache.fill(0.0);
+        self.finite_horizon_success = None;
     }
 
     pub fn derive_policy(
@@ -349,14 +1232,213 @@ impl RerollPolicySolver {
         self.reset_policy_cache();
 
         let p_success_all: f64 = self.success_count as f64 / NUM_FULL_MASKS as f64;
-        let init_value = lock_cost(0) / p_success_all;
+        let init_value = self.lock_cost(0) / p_success_all;
 
         for (index, dp) in self.dp.iter_mut().enumerate() {
             *dp = if self.success[index] { 0.0 } else { init_value };
         }
 
+        self.value_iteration(tol, max_iter)
+    }
+
+    /// Like `derive_policy`, but start value iteration from the last converged dp.
+    ///
+    /// Useful when scanning nearby targets. Falls back to `derive_policy` without a prior policy.
+    pub fn derive_policy_warm(
+        &mut self,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<(), RerollPolicySolverError> {
+        let Some(previous_success_count) = self.converged_success_count else {
+            return self.derive_policy(tol, max_iter);
+        };
+        if !self.is_target_set() {
+            return Err(RerollPolicySolverError::TargetNotSet);
+        }
+        if tol.is_nan() || tol.is_infinite() || tol <= 0.0 {
+            return Err(RerollPolicySolverError::InvalidTolerance { tolerance: tol });
+        }
+        self.reset_policy_cache();
+
+        // The expected cost scales roughly with 1 / P(success), so rescale the old values.
+        let p_success_all: f64 = self.success_count as f64 / NUM_FULL_MASKS as f64;
+        let init_value = self.lock_cost(0) / p_success_all;
+        let scale = previous_success_count as f64 / self.success_count as f64;
+        for (index, dp) in self.dp.iter_mut().enumerate() {
+            *dp = if self.success[index] {
+                0.0
+            } else if *dp == 0.0 {
+                // Was a success mask under the previous target.
+                init_value
+            } else {
+                *dp * scale
+            };
+        }
+
+        self.value_iteration(tol, max_iter)
+    }
+
+    fn value_iteration(
+        &mut self,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<(), RerollPolicySolverError> {
+        self.converged_success_count = None;
+        self.value_iterations = None;
         let mut next = self.dp;
+        let parallel = NUM_FULL_MASKS - self.success_count >= self.parallel_threshold;
+
+        for iter in 0..max_iter {
+            let max_delta = if parallel {
+                next.par_iter_mut()
+                    .enumerate()
+                    .map(|(index, value)| self.bellman_update(index, value))
+                    .reduce(|| 0.0, f64::max)
+            } else {
+                next.iter_mut()
+                    .enumerate()
+                    .map(|(index, value)| self.bellman_update(index, value))
+                    .fold(0.0, f64::max)
+            };
+            self.dp = next;
+            log::trace!("reroll value iteration: iter={iter} max_delta={max_delta}");
+            if max_delta <= tol {
+                log::debug!(
+                    "reroll value iteration: converged after {} iterations, success_count={}",
+                    iter + 1,
+                    self.success_count
+                );
+                self.build_action_cache();
+                self.policy_derived = true;
+                self.converged_success_count = Some(self.success_count);
+                self.value_iterations = Some(iter + 1);
+                return Ok(());
+            }
+        }
 
+        log::debug!("reroll value iteration: no convergence within {max_iter} iterations");
+        Err(RerollPolicySolverError::FailedtoConvergeWithinMaxIter)
+    }
+}
+
+impl RerollPolicySolver {
+    /// Maximize the probability of reaching the target within `max_rerolls` rerolls,
+    /// ignoring lock costs, for `finite_horizon_success`.
+    ///
+    /// Runs exactly `max_rerolls` backward steps. Independent of `derive_policy`.
+    pub fn derive_finite_horizon(
+        &mut self,
+        max_rerolls: usize,
+    ) -> Result<(), RerollPolicySolverError> {
+        if !self.is_target_set() {
+            return Err(RerollPolicySolverError::TargetNotSet);
+        }
+
+        let mut values = [0.0f64; NUM_FULL_MASKS];
+        for (index, value) in values.iter_mut().enumerate() {
+            *value = if self.success[index] { 1.0 } else { 0.0 };
+        }
+        for _ in 0..max_rerolls {
+            let mut next = values;
+            next.par_iter_mut().enumerate().for_each(|(index, value)| {
+                if self.success[index] {
+                    return;
+                }
+                let baseline = values[index];
+                for &lock_mask in self.lock_sets[index].iter() {
+                    let candidates = &self.transitions[lock_mask as usize];
+                    let total: f64 = candidates
+                        .iter()
+                        .map(|&candidate_index| values[candidate_index].max(baseline))
+                        .sum();
+                    *value = (*value).max(total / candidates.len() as f64);
+                }
+            });
+            values = next;
+        }
+        self.finite_horizon_success = Some(values);
+        Ok(())
+    }
+
+    /// The probability that `mask` reaches the target within the rerolls given to
+    /// `derive_finite_horizon`, keeping the better echo after each reroll.
+    pub fn finite_horizon_success(&self, mask: u16) -> Result<f64, RerollPolicySolverError> {
+        let Some(values) = &self.finite_horizon_success else {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        };
+        if !is_valid_external_full_mask(mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask });
+        }
+        Ok(values[full_mask_to_index(mask)])
+    }
+}
+
+impl RerollPolicySolver {
+    #[inline(always)]
+    fn threshold_action_value(
+        &self,
+        dp: &[f64; NUM_FULL_MASKS],
+        accept: &[bool; NUM_FULL_MASKS],
+        baseline_dp: f64,
+        lock_mask: u16,
+    ) -> f64 {
+        let k = calculate_num_filled_slots(lock_mask);
+        let candidates = &self.transitions[lock_mask as usize];
+        let mut total: f64 = 0.0;
+        for &candidate_index in candidates.iter() {
+            total += if accept[candidate_index] {
+                dp[candidate_index]
+            } else {
+                baseline_dp
+            };
+        }
+        let expected = total / candidates.len() as f64;
+        self.lock_cost(k) + expected
+    }
+
+    /// The expected cost from `mask` when a candidate is kept iff its score is at least
+    /// `accept_threshold` (or it reaches the target), with locks still chosen optimally.
+    ///
+    /// Like the target, `accept_threshold` is in fixed score units and includes the main
+    /// stat.
+    ///
+    /// Compare with `expected_lock_cost` to see how much a fixed accept rule loses.
+    pub fn expected_cost_under_policy(
+        &self,
+        accept_threshold: f64,
+        mask: u16,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<f64, RerollPolicySolverError> {
+        if !self.is_target_set() {
+            return Err(RerollPolicySolverError::TargetNotSet);
+        }
+        if accept_threshold.is_nan() {
+            return Err(RerollPolicySolverError::InvalidAcceptThreshold {
+                threshold: accept_threshold,
+            });
+        }
+        if tol.is_nan() || tol.is_infinite() || tol <= 0.0 {
+            return Err(RerollPolicySolverError::InvalidTolerance { tolerance: tol });
+        }
+        if !is_valid_external_full_mask(mask) {
+            return Err(RerollPolicySolverError::InvalidMask { mask });
+        }
+
+        let mut accept = [false; NUM_FULL_MASKS];
+        for (index, &score) in self.scores.iter().enumerate() {
+            accept[index] =
+                self.success[index] || f64::from(score) + self.main_stat_score >= accept_threshold;
+        }
+
+        let p_success_all: f64 = self.success_count as f64 / NUM_FULL_MASKS as f64;
+        let init_value = self.lock_cost(0) / p_success_all;
+        let mut dp = [0.0; NUM_FULL_MASKS];
+        for (index, value) in dp.iter_mut().enumerate() {
+            *value = if self.success[index] { 0.0 } else { init_value };
+        }
+
+        let mut next = dp;
         for _ in 0..max_iter {
             let max_delta = next
                 .par_iter_mut()
@@ -365,27 +1447,323 @@ impl RerollPolicySolver {
                     if self.success[index] {
                         return 0.0;
                     }
-
-                    let baseline_dp = self.dp[index];
+                    let baseline_dp = dp[index];
                     let mut best = f64::INFINITY;
                     for &lock_mask in self.lock_sets[index].iter() {
-                        let dp = self.action_value(baseline_dp, lock_mask);
-                        if dp < best {
-                            best = dp;
+                        let cost =
+                            self.threshold_action_value(&dp, &accept, baseline_dp, lock_mask);
+                        if cost < best {
+                            best = cost;
                         }
                     }
                     *value = best;
-                    (best - self.dp[index]).abs()
+                    (best - baseline_dp).abs()
                 })
                 .reduce(|| 0.0, f64::max);
-            self.dp = next;
+            dp = next;
             if max_delta <= tol {
-                self.build_action_cache();
-                self.policy_derived = true;
-                return Ok(());
+                return Ok(dp[full_mask_to_index(mask)]);
             }
         }
 
         Err(RerollPolicySolverError::FailedtoConvergeWithinMaxIter)
     }
 }
+
+/// Binary policy layout (little endian):
+/// magic, version `u8`, `NUM_BUFFS` as `u8`, `NUM_FULL_MASKS` as `u32`, target `u16`,
+/// main stat score `f64`, `NUM_ECHO_SLOTS` lock costs as `f64`, then `NUM_FULL_MASKS` dp
+/// values as `f64` and `NUM_FULL_MASKS` best lock masks as `u16`.
+impl RerollPolicySolver {
+    pub fn serialize_policy_bin(&self) -> Result<Vec<u8>, RerollPolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(RerollPolicySolverError::PolicyNotDerived);
+        }
+        let target_score = self
+            .target_score
+            .ok_or(RerollPolicySolverError::TargetNotSet)?;
+        if self.target_band.is_some() {
+            return Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "banded targets cannot be serialized",
+            });
+        }
+
+        let mut bytes = Vec::with_capacity(20 + NUM_ECHO_SLOTS * 8 + NUM_FULL_MASKS * 10);
+        bytes.extend_from_slice(POLICY_BIN_MAGIC);
+        bytes.push(POLICY_BIN_VERSION);
+        bytes.push(NUM_BUFFS as u8);
+        bytes.extend_from_slice(&(NUM_FULL_MASKS as u32).to_le_bytes());
+        bytes.extend_from_slice(&target_score.to_le_bytes());
+        bytes.extend_from_slice(&self.main_stat_score.to_le_bytes());
+        for cost in self.lock_costs.iter() {
+            bytes.extend_from_slice(&cost.to_le_bytes());
+        }
+        for dp in self.dp.iter() {
+            bytes.extend_from_slice(&dp.to_le_bytes());
+        }
+        for best_lock in self.best_lock_cache.iter() {
+            bytes.extend_from_slice(&best_lock.unwrap_or(POLICY_BIN_NO_LOCK).to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Load a policy written by `serialize_policy_bin` for the same weights, main stat and
+    /// lock costs.
+    ///
+    /// The lock choices are rebuilt from the stored dp and must reproduce the stored best
+    /// locks, which catches policies saved with different weights. The solver is left
+    /// unchanged if the file is rejected.
+    pub fn load_policy_bin(&mut self, bytes: &[u8]) -> Result<(), RerollPolicySolverError> {
+        let mut reader = PolicyBinReader { bytes };
+        if reader.take(POLICY_BIN_MAGIC.len())? != POLICY_BIN_MAGIC {
+            return Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "bad magic",
+            });
+        }
+        if reader.take(1)?[0] != POLICY_BIN_VERSION {
+            return Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "unsupported version",
+            });
+        }
+        if reader.take(1)?[0] as usize != NUM_BUFFS {
+            return Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "buff count mismatch",
+            });
+        }
+        if reader.read_u32()? as usize != NUM_FULL_MASKS {
+            return Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "mask count mismatch",
+            });
+        }
+        let target_score = reader.read_u16()?;
+        if reader.read_f64()?.to_bits() != self.main_stat_score.to_bits() {
+            return Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "main stat mismatch",
+            });
+        }
+        for &cost in self.lock_costs.iter() {
+            if reader.read_f64()?.to_bits() != cost.to_bits() {
+                return Err(RerollPolicySolverError::InvalidPolicyBin {
+                    reason: "lock costs mismatch",
+                });
+            }
+        }
+        let mut dp = [0.0; NUM_FULL_MASKS];
+        for value in dp.iter_mut() {
+            *value = reader.read_f64()?;
+        }
+        let mut best_lock_cache = [None; NUM_FULL_MASKS];
+        for best_lock in best_lock_cache.iter_mut() {
+            let lock_mask = reader.read_u16()?;
+            *best_lock = (lock_mask != POLICY_BIN_NO_LOCK).then_some(lock_mask);
+        }
+        if !reader.bytes.is_empty() {
+            return Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "trailing bytes",
+            });
+        }
+
+        self.validate_target(target_score)?;
+        let (success, _) = self.success_where(|score| score >= f64::from(target_score));
+        if self.best_locks_in(&dp, &success) != best_lock_cache {
+            return Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "best locks do not match the weights",
+            });
+        }
+
+        self.set_target(target_score)?;
+        self.dp = dp;
+        self.build_action_cache();
+        self.policy_derived = true;
+        self.converged_success_count = Some(self.success_count);
+        self.value_iterations = None;
+        Ok(())
+    }
+}
+
+struct PolicyBinReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> PolicyBinReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], RerollPolicySolverError> {
+        if self.bytes.len() < len {
+            return Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "unexpected end of data",
+            });
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, RerollPolicySolverError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, RerollPolicySolverError> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn read_f64(&mut self) -> Result<f64, RerollPolicySolverError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(f64::from_le_bytes(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranked_weights() -> [u16; NUM_BUFFS] {
+        std::array::from_fn(|i| (i as u16 + 1) * 100)
+    }
+
+    #[test]
+    fn expected_score_of_mask_depends_on_values() {
+        let solver = RerollPolicySolver::new(ranked_weights()).unwrap();
+        let mask = 0b1_1111_0000_0000u16;
+        let fixed_score = solver.scores[full_mask_to_index(mask)] as f64 / SCORE_MULTIPLIER;
+
+        let expected_score = solver.expected_score_of_mask(mask).unwrap();
+        assert!(expected_score > 0.0);
+        assert!(expected_score < fixed_score - 1.0);
+    }
+
+    #[test]
+    fn rejected_policy_bin_keeps_the_loaded_policy() {
+        let mut other_weights = ranked_weights();
+        other_weights.reverse();
+        let mut other = RerollPolicySolver::new(other_weights).unwrap();
+        other.set_target(3000).unwrap();
+        other.derive_policy(1e-4, 200).unwrap();
+        let bytes = other.serialize_policy_bin().unwrap();
+
+        let mut solver = RerollPolicySolver::new(ranked_weights()).unwrap();
+        solver.set_target(4000).unwrap();
+        solver.derive_policy(1e-4, 200).unwrap();
+        let dp = solver.dp;
+        let best_locks = solver.best_lock_cache;
+        assert!(matches!(
+            solver.load_policy_bin(&bytes),
+            Err(RerollPolicySolverError::InvalidPolicyBin { .. })
+        ));
+        assert!(solver.is_policy_derived());
+        assert_eq!(solver.target_score, Some(4000));
+        assert_eq!(solver.dp, dp);
+        assert_eq!(solver.best_lock_cache, best_locks);
+
+        let bytes = solver.serialize_policy_bin().unwrap();
+        let mut reloaded = RerollPolicySolver::new(ranked_weights()).unwrap();
+        reloaded.load_policy_bin(&bytes).unwrap();
+        assert_eq!(reloaded.best_lock_cache, best_locks);
+    }
+
+    #[test]
+    fn zero_weight_buff_is_never_locked() {
+        let mut weights = ranked_weights();
+        weights[0] = 0;
+        let mut solver = RerollPolicySolver::new(weights).unwrap();
+        solver.set_target(4500).unwrap();
+        solver.derive_policy(1e-4, 200).unwrap();
+        let baseline = 0b0_0000_0001_1111u16;
+        let index = full_mask_to_index(baseline);
+        assert!(!solver.success[index]);
+        assert!(solver.dp[index].is_finite() && solver.dp[index] > 0.0);
+
+        let choices = solver.lock_choices(baseline, 0).unwrap();
+        assert!(!choices.is_empty());
+        assert!(choices.iter().all(|choice| choice.lock_mask & 1 == 0));
+    }
+
+    #[test]
+    fn warm_start_takes_fewer_sweeps() {
+        // The desktop app's default fixed weights.
+        let weights = [3, 3, 1, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0];
+        let mut cold = RerollPolicySolver::new(weights).unwrap();
+        cold.set_target(7).unwrap();
+        cold.derive_policy(1e-6, 1000).unwrap();
+        let cold_sweeps = cold.value_iterations().unwrap();
+
+        let mut warm = RerollPolicySolver::new(weights).unwrap();
+        warm.set_target(8).unwrap();
+        warm.derive_policy(1e-6, 1000).unwrap();
+        warm.set_target(7).unwrap();
+        warm.derive_policy_warm(1e-6, 1000).unwrap();
+        let warm_sweeps = warm.value_iterations().unwrap();
+        assert!(warm_sweeps > 0 && warm_sweeps < cold_sweeps);
+    }
+
+    #[test]
+    fn expected_score_gain_is_in_display_units() {
+        let solver = RerollPolicySolver::new(ranked_weights()).unwrap();
+        let baseline_mask = 0b0_0000_0001_1111u16;
+        let candidate_mask = 0b1_1111_0000_0000u16;
+        let fixed_gain = (solver.scores[full_mask_to_index(candidate_mask)]
+            - solver.scores[full_mask_to_index(baseline_mask)]) as f64
+            / SCORE_MULTIPLIER;
+
+        let gain = solver
+            .expected_score_gain(baseline_mask, candidate_mask)
+            .unwrap();
+        assert!(gain > 0.0);
+        assert!(gain < fixed_gain);
+        let difference = solver.expected_score_of_mask(candidate_mask).unwrap()
+            - solver.expected_score_of_mask(baseline_mask).unwrap();
+        assert!((gain - difference).abs() < 1e-12);
+    }
+    #[test]
+    fn accept_threshold_includes_the_main_stat() {
+        let mut with_main_stat = RerollPolicySolver::new(ranked_weights()).unwrap();
+        with_main_stat.set_main_stat(500.0).unwrap();
+        with_main_stat.set_target(4000).unwrap();
+        let mut without_main_stat = RerollPolicySolver::new(ranked_weights()).unwrap();
+        without_main_stat.set_target(3500).unwrap();
+
+        let mask = 0b0_0000_0001_1111u16;
+        let shifted = with_main_stat
+            .expected_cost_under_policy(3500.0, mask, 1e-6, 1000)
+            .unwrap();
+        let unshifted = without_main_stat
+            .expected_cost_under_policy(3000.0, mask, 1e-6, 1000)
+            .unwrap();
+        assert!((shifted - unshifted).abs() < 1e-9);
+    }
+    #[test]
+    fn policy_bin_records_main_stat_and_lock_costs() {
+        let mut solver = RerollPolicySolver::new(ranked_weights()).unwrap();
+        solver.set_main_stat(500.0).unwrap();
+        solver.set_lock_costs([1.0, 1.0, 2.0, 3.0, 5.0]).unwrap();
+        solver.set_target(4000).unwrap();
+        solver.derive_policy(1e-4, 200).unwrap();
+        let bytes = solver.serialize_policy_bin().unwrap();
+
+        let mut default_settings = RerollPolicySolver::new(ranked_weights()).unwrap();
+        assert!(matches!(
+            default_settings.load_policy_bin(&bytes),
+            Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "main stat mismatch"
+            })
+        ));
+        default_settings.set_main_stat(500.0).unwrap();
+        assert!(matches!(
+            default_settings.load_policy_bin(&bytes),
+            Err(RerollPolicySolverError::InvalidPolicyBin {
+                reason: "lock costs mismatch"
+            })
+        ));
+
+        let mut same_settings = RerollPolicySolver::new(ranked_weights()).unwrap();
+        same_settings.set_main_stat(500.0).unwrap();
+        same_settings
+            .set_lock_costs([1.0, 1.0, 2.0, 3.0, 5.0])
+            .unwrap();
+        same_settings.load_policy_bin(&bytes).unwrap();
+        assert_eq!(same_settings.dp, solver.dp);
+        assert_eq!(same_settings.best_lock_cache, solver.best_lock_cache);
+    }
+}
@@ -1,15 +1,89 @@
 use std::collections::BTreeMap;
+use std::sync::OnceLock;
 
 use crate::data::{BUFF_FIXED_VALUE_INDEX, BUFF_MAX_VALUES, BUFF_TYPES, NUM_BUFFS, NUM_ECHO_SLOTS};
+use crate::mask::{MASK_ALL, bits_to_mask};
 
 const BLEND_GROUP_CRIT: [usize; 2] = [0, 1];
 const BLEND_GROUP_MAIN: [usize; 9] = [2, 3, 4, 7, 8, 9, 10, 11, 12];
 
 pub const SCORE_MULTIPLIER: f64 = 100.0;
-const MAX_DISPLAY_SCORE: f64 = u16::MAX as f64 / SCORE_MULTIPLIER;
 
-pub fn convert_display_to_internal(score_display: f64) -> u16 {
-    (score_display * SCORE_MULTIPLIER).round() as u16
+/// An internal score, i.e. a display score times the scorer's `score_multiplier`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RawScore(u16);
+
+impl RawScore {
+    pub const fn new(raw: u16) -> Self {
+        Self(raw)
+    }
+
+    pub const fn into_raw(self) -> u16 {
+        self.0
+    }
+
+    pub fn to_display(self, score_multiplier: f64) -> DisplayScore {
+        DisplayScore(self.0 as f64 / score_multiplier)
+    }
+}
+
+impl From<u16> for RawScore {
+    fn from(raw: u16) -> Self {
+        Self(raw)
+    }
+}
+
+impl From<RawScore> for u16 {
+    fn from(score: RawScore) -> Self {
+        score.0
+    }
+}
+
+/// A score as shown to users, see `RawScore`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct DisplayScore(f64);
+
+impl DisplayScore {
+    pub const fn new(display: f64) -> Self {
+        Self(display)
+    }
+
+    pub const fn value(self) -> f64 {
+        self.0
+    }
+
+    /// The rounded raw score, or `None` if the score is negative, not finite or does not
+    /// fit a `RawScore` under `score_multiplier`.
+    pub fn to_raw(self, score_multiplier: f64) -> Option<RawScore> {
+        let raw = (self.0 * score_multiplier).round();
+        if !raw.is_finite() || raw < 0.0 || raw > u16::MAX as f64 {
+            return None;
+        }
+        Some(RawScore(raw as u16))
+    }
+}
+
+impl From<f64> for DisplayScore {
+    fn from(display: f64) -> Self {
+        Self(display)
+    }
+}
+
+impl From<DisplayScore> for f64 {
+    fn from(score: DisplayScore) -> Self {
+        score.0
+    }
+}
+
+pub fn convert_display_to_internal(score_display: f64, score_multiplier: f64) -> u16 {
+    (score_display * score_multiplier).round() as u16
+}
+
+fn validate_score_multiplier(score_multiplier: f64) -> Result<(), ScorerError> {
+    if !score_multiplier.is_finite() || score_multiplier <= 0.0 {
+        return Err(ScorerError::InvalidScoreMultiplier { score_multiplier });
+    }
+    Ok(())
 }
 
 fn is_valid_buff(buff_index: usize, buff_value: u16) -> Result<(), ScorerError> {
@@ -38,11 +112,144 @@ pub enum ScorerError {
     InvalidUnnormalizedMaxScore { unnormalized_max_score: f64 },
     InvalidEcho,
     FixedScorerTopWeightsTooLarge { sum: u32 },
+    FixedScorerWeightScalesToZero { buff_index: usize, weight: u16 },
+    FixedScorerWeightTooLarge { buff_index: usize, scaled: f64 },
+    InvalidScoreMultiplier { score_multiplier: f64 },
+}
+
+impl std::fmt::Display for ScorerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NegativeWeight { index, weight } => {
+                write!(
+                    f,
+                    "weight {index} must be a non-negative number, got {weight}"
+                )
+            }
+            Self::AllWeightsZero => write!(f, "at least one buff weight must be positive"),
+            Self::InvalidBuffIndex {
+                buff_index,
+                buff_value,
+            } => {
+                write!(f, "invalid buff index {buff_index} (value {buff_value})")
+            }
+            Self::InvalidBuffValue {
+                buff_index,
+                buff_value,
+            } => {
+                write!(f, "invalid value {buff_value} for buff {buff_index}")
+            }
+            Self::InvalidMainBuffScore { main_buff_score } => {
+                write!(
+                    f,
+                    "main buff score must be a non-negative number, got {main_buff_score}"
+                )
+            }
+            Self::InvalidNormalizedMaxScore {
+                normalized_max_score,
+            } => write!(
+                f,
+                "normalized max score must be a positive number, got {normalized_max_score}"
+            ),
+            Self::InvalidUnnormalizedMaxScore {
+                unnormalized_max_score,
+            } => write!(
+                f,
+                "unnormalized max score must be a positive number, got {unnormalized_max_score}"
+            ),
+            Self::InvalidEcho => write!(f, "invalid echo"),
+            Self::FixedScorerTopWeightsTooLarge { sum } => {
+                write!(f, "sum of the top fixed weights is too large: {sum}")
+            }
+            Self::FixedScorerWeightScalesToZero { buff_index, weight } => {
+                write!(
+                    f,
+                    "fixed weight {weight} of buff {buff_index} rounds to 0 under the score multiplier"
+                )
+            }
+            Self::FixedScorerWeightTooLarge { buff_index, scaled } => {
+                write!(
+                    f,
+                    "scaled fixed weight of buff {buff_index} is too large: {scaled}"
+                )
+            }
+            Self::InvalidScoreMultiplier { score_multiplier } => {
+                write!(
+                    f,
+                    "score multiplier must be a positive number, got {score_multiplier}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScorerError {}
+
+#[derive(Debug)]
+pub enum EchoParseError {
+    WrongCount { count: usize },
+    LengthMismatch { names: usize, values: usize },
+    UnknownName { name: String },
+    DuplicateBuff { name: String },
+    InvalidBuff { error: ScorerError },
+}
+
+/// Turn a fully revealed echo, given as `buff_name`s and their values, into its full mask
+/// and internal score under `scorer`.
+pub fn parse_echo<S: InternalScorer + ?Sized>(
+    buff_names: &[&str],
+    buff_values: &[u16],
+    scorer: &S,
+) -> Result<(u16, u16), EchoParseError> {
+    if buff_names.len() != buff_values.len() {
+        return Err(EchoParseError::LengthMismatch {
+            names: buff_names.len(),
+            values: buff_values.len(),
+        });
+    }
+    if buff_names.len() != NUM_ECHO_SLOTS {
+        return Err(EchoParseError::WrongCount {
+            count: buff_names.len(),
+        });
+    }
+
+    let mut bits = [0u8; NUM_BUFFS];
+    let mut echo = Vec::with_capacity(NUM_ECHO_SLOTS);
+    for (&name, &buff_value) in buff_names.iter().zip(buff_values.iter()) {
+        let buff_index = BUFF_TYPES
+            .iter()
+            .position(|buff| buff.name == name)
+            .ok_or_else(|| EchoParseError::UnknownName {
+                name: name.to_string(),
+            })?;
+        if bits[buff_index] == 1 {
+            return Err(EchoParseError::DuplicateBuff {
+                name: name.to_string(),
+            });
+        }
+        bits[buff_index] = 1;
+        echo.push((buff_index, buff_value));
+    }
+
+    let score = scorer
+        .echo_score_internal(&echo)
+        .map_err(|error| EchoParseError::InvalidBuff { error })?;
+    Ok((bits_to_mask(&bits), score))
 }
 
 pub trait InternalScorer {
     fn buff_score_internal(&self, buff_index: usize, buff_value: u16) -> Result<u16, ScorerError>;
 
+    /// Internal score units per display score unit.
+    fn score_multiplier(&self) -> f64 {
+        SCORE_MULTIPLIER
+    }
+
+    /// The display weight of every buff, if the scorer is defined by per-buff weights.
+    fn buff_weights(&self) -> Option<[f64; NUM_BUFFS]> {
+        None
+    }
+
     fn echo_score_internal(&self, echo: &[(usize, u16)]) -> Result<u16, ScorerError> {
         if echo.len() > NUM_ECHO_SLOTS {
             return Err(ScorerError::InvalidEcho);
@@ -68,6 +275,22 @@ pub trait InternalScorer {
     }
 }
 
+/// Score PMFs built by `build_score_pmfs`, one slot per `blend_data` value.
+///
+/// Holds up to two copies of the PMFs (a few KB) for the lifetime of the scorer.
+#[derive(Debug, Default)]
+struct PmfCache([OnceLock<ScorePmfs>; 2]);
+
+type ScorePmfs = Vec<Vec<(u16, f64)>>;
+
+impl PmfCache {
+    fn get_or_build<S: InternalScorer + ?Sized>(&self, scorer: &S, blend_data: bool) -> ScorePmfs {
+        self.0[blend_data as usize]
+            .get_or_init(|| build_score_pmfs(scorer, blend_data))
+            .clone()
+    }
+}
+
 fn validate_weights(weights: &[f64; NUM_BUFFS]) -> Result<(), ScorerError> {
     let mut any_positive = false;
     for (index, &weight) in weights.iter().enumerate() {
@@ -118,6 +341,46 @@ fn fixed_scorer_top_weights_sum(weights: &[u16; NUM_BUFFS]) -> u32 {
     top_weights.into_iter().map(|w| w as u32).sum()
 }
 
+/// The smallest uniform weight over the `allowed` buff mask, 0 elsewhere, whose best echo
+/// of allowed buffs at their histogram max rolls reaches the display `target`.
+///
+/// Scores are normalized against all buffs at the maximum weight 1, so five max rolls of
+/// weight 1 score 100 and an echo with fewer allowed buffs scores less. The weights score
+/// like a `LinearScorer` without main buff whose normalized max score is 20 times the sum
+/// of the top five weights.
+/// `None` if `allowed` is empty or out of range, `target` is not positive, or the target
+/// is out of reach even at weight 1.
+pub fn min_weights_for_target(allowed: u16, target: f64) -> Option<[f64; NUM_BUFFS]> {
+    if allowed == 0 || (allowed & !MASK_ALL) != 0 {
+        return None;
+    }
+    if !target.is_finite() || target <= 0.0 {
+        return None;
+    }
+
+    let mut max_roll_ratios = [0.0f64; NUM_BUFFS];
+    for (buff_index, buff) in BUFF_TYPES.iter().enumerate() {
+        if (allowed & (1u16 << buff_index)) == 0 {
+            continue;
+        }
+        let max_roll = buff.histogram.iter().map(|&(value, _)| value).max()?;
+        max_roll_ratios[buff_index] = max_roll as f64 / BUFF_MAX_VALUES[buff_index] as f64;
+    }
+
+    let max_score = 100.0 * top_weights_sum(&max_roll_ratios) / top_weights_sum(&[1.0; NUM_BUFFS]);
+    if max_score < target {
+        return None;
+    }
+    let weight = target / max_score;
+    let mut weights = [0.0f64; NUM_BUFFS];
+    for (buff_index, value) in weights.iter_mut().enumerate() {
+        if (allowed & (1u16 << buff_index)) != 0 {
+            *value = weight;
+        }
+    }
+    Some(weights)
+}
+
 /// Calculate the sum of the highest weights.
 fn top_weights_sum(weights: &[f64; NUM_BUFFS]) -> f64 {
     let mut top_weights: [f64; NUM_ECHO_SLOTS] = [0.0; NUM_ECHO_SLOTS];
@@ -137,7 +400,10 @@ fn top_weights_sum(weights: &[f64; NUM_BUFFS]) -> f64 {
 
 pub struct FixedScorer {
     weights: [u16; NUM_BUFFS],
+    internal_weights: [u16; NUM_BUFFS],
     max_score: u16,
+    score_multiplier: f64,
+    pmf_cache: Option<PmfCache>,
 }
 
 impl FixedScorer {
@@ -146,7 +412,56 @@ impl FixedScorer {
     // If new error paths are added here, update that mapping accordingly.
     pub fn new(weights: [u16; NUM_BUFFS]) -> Result<Self, ScorerError> {
         let max_score = validate_fixed_scorer_weights(&weights)?;
-        Ok(Self { weights, max_score })
+        Ok(Self {
+            weights,
+            internal_weights: weights,
+            max_score,
+            score_multiplier: SCORE_MULTIPLIER,
+            pmf_cache: None,
+        })
+    }
+
+    /// Like `new`, but scores in units of `1 / score_multiplier` display points.
+    ///
+    /// `buff_score_display` and `echo_score_display` still return the raw weights and weight
+    /// sums; each weight is rescaled to `weight * score_multiplier / SCORE_MULTIPLIER`
+    /// internally, rounded to the nearest integer, so ratios between buffs are only kept
+    /// up to that rounding. A non-zero weight that rounds to 0 is rejected. A smaller
+    /// multiplier (e.g. 10.0) admits weights whose top sum exceeds `u16::MAX` at the cost
+    /// of coarser score bins.
+    pub fn new_with_score_multiplier(
+        weights: [u16; NUM_BUFFS],
+        score_multiplier: f64,
+    ) -> Result<Self, ScorerError> {
+        validate_score_multiplier(score_multiplier)?;
+        let mut internal_weights = [0u16; NUM_BUFFS];
+        for (buff_index, (internal_weight, &weight)) in
+            internal_weights.iter_mut().zip(weights.iter()).enumerate()
+        {
+            let scaled = (weight as f64 * score_multiplier / SCORE_MULTIPLIER).round();
+            if scaled > u16::MAX as f64 {
+                return Err(ScorerError::FixedScorerWeightTooLarge { buff_index, scaled });
+            }
+            if weight != 0 && scaled == 0.0 {
+                return Err(ScorerError::FixedScorerWeightScalesToZero { buff_index, weight });
+            }
+            *internal_weight = scaled as u16;
+        }
+        let max_score = validate_fixed_scorer_weights(&internal_weights)?;
+        Ok(Self {
+            weights,
+            internal_weights,
+            max_score,
+            score_multiplier,
+            pmf_cache: None,
+        })
+    }
+
+    /// Keep the score PMFs after the first `build_score_pmfs` call for each `blend_data`,
+    /// so solvers built repeatedly from this scorer skip the rebuild.
+    pub fn with_pmf_cache(mut self) -> Self {
+        self.pmf_cache = Some(PmfCache::default());
+        self
     }
 
     pub fn build_from_buff_selection() -> Result<Self, ScorerError> {
@@ -166,18 +481,40 @@ impl FixedScorer {
         buff_index: usize,
         buff_value: u16,
     ) -> Result<u16, ScorerError> {
-        self.buff_score_internal(buff_index, buff_value)
+        is_valid_buff(buff_index, buff_value)?;
+        Ok(self.weights[buff_index])
     }
 
+    /// The echo's weight sum, which only exceeds `u16::MAX` under a custom score multiplier.
     pub fn echo_score_display(&self, echo: &[(usize, u16)]) -> Result<u16, ScorerError> {
-        self.echo_score_internal(echo)
+        self.echo_score_internal(echo)?;
+        let sum: u32 = echo
+            .iter()
+            .map(|&(buff_index, _)| self.weights[buff_index] as u32)
+            .sum();
+        u16::try_from(sum).map_err(|_| ScorerError::FixedScorerTopWeightsTooLarge { sum })
     }
 }
 
 impl InternalScorer for FixedScorer {
     fn buff_score_internal(&self, buff_index: usize, buff_value: u16) -> Result<u16, ScorerError> {
         is_valid_buff(buff_index, buff_value)?;
-        Ok(self.weights[buff_index])
+        Ok(self.internal_weights[buff_index])
+    }
+
+    fn score_multiplier(&self) -> f64 {
+        self.score_multiplier
+    }
+
+    fn buff_weights(&self) -> Option<[f64; NUM_BUFFS]> {
+        Some(self.weights.map(f64::from))
+    }
+
+    fn build_score_pmfs(&self, blend_data: bool) -> Vec<Vec<(u16, f64)>> {
+        match &self.pmf_cache {
+            Some(cache) => cache.get_or_build(self, blend_data),
+            None => build_score_pmfs(self, blend_data),
+        }
     }
 }
 
@@ -186,6 +523,8 @@ pub struct LinearScorer {
     unnormalized_max_score: f64,
     normalized_main_buff_score: f64,
     normalized_max_score: f64,
+    score_multiplier: f64,
+    pmf_cache: Option<PmfCache>,
 }
 
 impl LinearScorer {
@@ -194,6 +533,24 @@ impl LinearScorer {
         main_buff_score: f64,
         normalized_max_score: f64,
     ) -> Result<Self, ScorerError> {
+        Self::new_with_score_multiplier(
+            weights,
+            main_buff_score,
+            normalized_max_score,
+            SCORE_MULTIPLIER,
+        )
+    }
+
+    /// Like `new`, but rounds scores to `1 / score_multiplier` display points instead of
+    /// 0.01. A smaller multiplier raises the `normalized_max_score` limit of
+    /// `u16::MAX / score_multiplier`.
+    pub fn new_with_score_multiplier(
+        weights: [f64; NUM_BUFFS],
+        main_buff_score: f64,
+        normalized_max_score: f64,
+        score_multiplier: f64,
+    ) -> Result<Self, ScorerError> {
+        validate_score_multiplier(score_multiplier)?;
         validate_weights(&weights)?;
         if main_buff_score.is_infinite() || main_buff_score.is_nan() || main_buff_score < 0.0 {
             return Err(ScorerError::InvalidMainBuffScore { main_buff_score });
@@ -201,7 +558,7 @@ impl LinearScorer {
         if normalized_max_score.is_infinite()
             || normalized_max_score.is_nan()
             || normalized_max_score <= 0.0
-            || normalized_max_score > MAX_DISPLAY_SCORE
+            || normalized_max_score > u16::MAX as f64 / score_multiplier
         {
             return Err(ScorerError::InvalidNormalizedMaxScore {
                 normalized_max_score,
@@ -222,6 +579,8 @@ impl LinearScorer {
             unnormalized_max_score,
             normalized_main_buff_score,
             normalized_max_score,
+            score_multiplier,
+            pmf_cache: None,
         })
     }
 
@@ -252,8 +611,17 @@ impl LinearScorer {
             unnormalized_max_score,
             normalized_main_buff_score: 50.0,
             normalized_max_score: 120.0,
+            score_multiplier: SCORE_MULTIPLIER,
+            pmf_cache: None,
         })
     }
+
+    /// Keep the score PMFs after the first `build_score_pmfs` call for each `blend_data`,
+    /// so solvers built repeatedly from this scorer skip the rebuild.
+    pub fn with_pmf_cache(mut self) -> Self {
+        self.pmf_cache = Some(PmfCache::default());
+        self
+    }
 }
 
 impl LinearScorer {
@@ -264,6 +632,13 @@ impl LinearScorer {
     pub fn normalized_max_score(&self) -> f64 {
         self.normalized_max_score
     }
+
+    /// Highest display score an echo can reach, main buff included.
+    pub fn max_echo_score(&self) -> f64 {
+        self.normalized_main_buff_score
+            + self.normalized_max_score * top_weights_sum(&self.weights)
+                / self.unnormalized_max_score
+    }
 }
 
 impl LinearScorer {
@@ -290,7 +665,25 @@ impl LinearScorer {
 impl InternalScorer for LinearScorer {
     fn buff_score_internal(&self, buff_index: usize, buff_value: u16) -> Result<u16, ScorerError> {
         let score_display = self.buff_score_display(buff_index, buff_value)?;
-        Ok(convert_display_to_internal(score_display))
+        Ok(convert_display_to_internal(
+            score_display,
+            self.score_multiplier,
+        ))
+    }
+
+    fn score_multiplier(&self) -> f64 {
+        self.score_multiplier
+    }
+
+    fn buff_weights(&self) -> Option<[f64; NUM_BUFFS]> {
+        Some(self.weights)
+    }
+
+    fn build_score_pmfs(&self, blend_data: bool) -> Vec<Vec<(u16, f64)>> {
+        match &self.pmf_cache {
+            Some(cache) => cache.get_or_build(self, blend_data),
+            None => build_score_pmfs(self, blend_data),
+        }
     }
 }
 
@@ -311,6 +704,28 @@ pub fn build_score_pmfs<S: InternalScorer + ?Sized>(
     }
 }
 
+/// The `(buff value, probability)` PMF of each buff, blended like `build_score_pmfs`.
+pub(crate) fn build_value_pmfs(blend_data: bool) -> Vec<Vec<(u16, f64)>> {
+    let histograms: Vec<Vec<(u16, u32)>> = if blend_data {
+        build_blended_histograms()
+    } else {
+        BUFF_TYPES
+            .iter()
+            .map(|buff| buff.histogram.to_vec())
+            .collect()
+    };
+    histograms
+        .iter()
+        .map(|histogram| {
+            let total_counts: f64 = histogram.iter().map(|&(_, c)| c as f64).sum();
+            histogram
+                .iter()
+                .map(|&(buff_value, count)| (buff_value, count as f64 / total_counts))
+                .collect()
+        })
+        .collect()
+}
+
 fn build_score_pmfs_from_histograms<S: InternalScorer + ?Sized>(
     scorer: &S,
     histograms: &[&[(u16, u32)]],
@@ -356,3 +771,121 @@ fn blend_group(blended: &mut [Vec<(u16, u32)>], group: &[usize]) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranked_scorer() -> FixedScorer {
+        FixedScorer::new(std::array::from_fn(|index| index as u16 + 1)).unwrap()
+    }
+
+    #[test]
+    fn parse_echo_returns_mask_and_score() {
+        let names = ["Crit. Rate", "Crit. DMG", "ATK%", "ATK", "Energy Regen"];
+        let values = [63, 126, 64, 30, 68];
+        let (mask, score) = parse_echo(&names, &values, &ranked_scorer()).unwrap();
+        assert_eq!(mask, 0b1_0010_0111);
+        assert_eq!(score, 1 + 2 + 3 + 6 + 9);
+    }
+
+    #[test]
+    fn parse_echo_rejects_malformed_echoes() {
+        let scorer = ranked_scorer();
+        let names = ["Crit. Rate", "Crit. DMG", "ATK%", "ATK", "Energy Regen"];
+        let values = [63, 126, 64, 30, 68];
+
+        assert!(matches!(
+            parse_echo(&names, &values[..4], &scorer),
+            Err(EchoParseError::LengthMismatch {
+                names: 5,
+                values: 4
+            })
+        ));
+        assert!(matches!(
+            parse_echo(&names[..4], &values[..4], &scorer),
+            Err(EchoParseError::WrongCount { count: 4 })
+        ));
+
+        let unknown = ["Crit. Rate", "Crit. DMG", "ATK%", "ATK", "Healing Bonus"];
+        assert!(matches!(
+            parse_echo(&unknown, &values, &scorer),
+            Err(EchoParseError::UnknownName { name }) if name == "Healing Bonus"
+        ));
+
+        let duplicate = ["Crit. Rate", "Crit. DMG", "ATK%", "ATK", "Crit. Rate"];
+        assert!(matches!(
+            parse_echo(&duplicate, &values, &scorer),
+            Err(EchoParseError::DuplicateBuff { name }) if name == "Crit. Rate"
+        ));
+
+        let too_large = [63, 126, 64, 30, BUFF_MAX_VALUES[8] + 1];
+        assert!(matches!(
+            parse_echo(&names, &too_large, &scorer),
+            Err(EchoParseError::InvalidBuff {
+                error: ScorerError::InvalidBuffValue { buff_index: 8, .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn score_multiplier_rejects_weights_it_cannot_scale() {
+        let mut weights = [1000u16; NUM_BUFFS];
+        weights[2] = 40;
+        assert!(matches!(
+            FixedScorer::new_with_score_multiplier(weights, 1.0),
+            Err(ScorerError::FixedScorerWeightScalesToZero {
+                buff_index: 2,
+                weight: 40
+            })
+        ));
+
+        weights[2] = 0;
+        let scorer = FixedScorer::new_with_score_multiplier(weights, 1.0).unwrap();
+        assert_eq!(
+            scorer.buff_score_internal(0, BUFF_MAX_VALUES[0]).unwrap(),
+            10
+        );
+        assert_eq!(
+            scorer.buff_score_display(0, BUFF_MAX_VALUES[0]).unwrap(),
+            1000
+        );
+
+        weights[5] = u16::MAX;
+        assert!(matches!(
+            FixedScorer::new_with_score_multiplier(weights, 1000.0),
+            Err(ScorerError::FixedScorerWeightTooLarge { buff_index: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn min_weights_for_target_checks_the_scorer() {
+        let crit_rate = 0b1;
+        let weights = min_weights_for_target(crit_rate, 10.0).unwrap();
+        assert!((weights[0] - 0.5).abs() < 1e-12);
+        assert!(weights[1..].iter().all(|&weight| weight == 0.0));
+
+        assert!(min_weights_for_target(crit_rate, 20.01).is_none());
+        assert!(min_weights_for_target(MASK_ALL, 100.01).is_none());
+        assert!(min_weights_for_target(MASK_ALL, 99.0).is_some());
+        assert!(min_weights_for_target(0, 1.0).is_none());
+    }
+
+    #[test]
+    fn min_weights_for_target_depends_on_the_allowed_buffs() {
+        let crits = 0b11;
+        let crits_and_atk = 0b111;
+        assert!(min_weights_for_target(crits, 60.0).is_none());
+        let weights = min_weights_for_target(crits_and_atk, 60.0).unwrap();
+        assert!(
+            weights[..3]
+                .iter()
+                .all(|&weight| (weight - 1.0).abs() < 1e-12)
+        );
+
+        let normalized_max_score = 100.0 * top_weights_sum(&weights) / 5.0;
+        let scorer = LinearScorer::new(weights, 0.0, normalized_max_score).unwrap();
+        let echo: Vec<(usize, u16)> = (0..3).map(|i| (i, BUFF_MAX_VALUES[i])).collect();
+        assert!((scorer.echo_score_display(&echo).unwrap() - 60.0).abs() < 1e-9);
+    }
+}
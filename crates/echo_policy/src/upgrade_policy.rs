@@ -1,12 +1,27 @@
-use crate::CostModel;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
 use crate::data::{NUM_BUFFS, NUM_ECHO_SLOTS};
 use crate::mask::{
-    MASK_ALL, NUM_PARTIAL_MASKS, PARTIAL_MASKS, calculate_num_filled_slots,
-    is_valid_external_full_mask, is_valid_external_partial_mask, partial_mask_to_index,
+    EchoMask, MASK_ALL, NUM_PARTIAL_MASKS, PARTIAL_MASKS, calculate_num_filled_slots,
+    is_valid_external_partial_mask, partial_mask_to_index,
 };
-use crate::scoring::{InternalScorer, convert_display_to_internal};
+use crate::scoring::{
+    DisplayScore, FixedScorer, InternalScorer, LinearScorer, RawScore, SCORE_MULTIPLIER,
+    ScorerError, build_value_pmfs, convert_display_to_internal,
+};
+use crate::{CostModel, CostModelError};
 
 const DP_VALUE_MULTIPLIER: f64 = 1000.0;
+const DEFAULT_MAX_LAMBDA_EXPANSIONS: usize = 80;
+/// Allowed deviation of a PMF's probability sum from 1.
+///
+/// Scorer-built PMFs are exact histogram ratios, so only summation rounding is tolerated.
+pub const DEFAULT_PMF_SUM_TOL: f64 = 1e-9;
+/// The most targets `UpgradePolicySolver::first_of` accepts.
+pub const MAX_FIRST_OF_TARGETS: usize = 4;
 
 fn best_case_remaining_score(mask: u16, buff_max_score: &[u16; NUM_BUFFS]) -> u16 {
     let num_filled_slots = calculate_num_filled_slots(mask);
@@ -40,18 +55,37 @@ struct ScorePmfAnalysis {
     max_possible_score: u16,
 }
 
-fn normalize_target_score(target_score_display: f64) -> Result<u16, UpgradePolicySolverError> {
+fn normalize_target_score(
+    target_score_display: f64,
+    score_multiplier: f64,
+) -> Result<u16, UpgradePolicySolverError> {
     if target_score_display.is_nan() || target_score_display.is_infinite() {
         return Err(UpgradePolicySolverError::InvalidScore);
     }
+    // The cast in `convert_display_to_internal` would saturate instead of failing.
+    if (target_score_display * score_multiplier).round() > u16::MAX as f64 {
+        return Err(UpgradePolicySolverError::TargetScoreOutOfRange {
+            target_score_display,
+        });
+    }
 
     Ok(if target_score_display <= 0.0 {
         0
     } else {
-        convert_display_to_internal(target_score_display)
+        convert_display_to_internal(target_score_display, score_multiplier)
     })
 }
 
+fn display_score_to_internal(
+    score_display: f64,
+    score_multiplier: f64,
+) -> Result<u16, UpgradePolicySolverError> {
+    DisplayScore::new(score_display)
+        .to_raw(score_multiplier)
+        .map(RawScore::into_raw)
+        .ok_or(UpgradePolicySolverError::InvalidScore)
+}
+
 fn validate_target_score(
     target_score: u16,
     max_possible_score: u16,
@@ -65,11 +99,10 @@ fn validate_target_score(
     Ok(())
 }
 
-fn analyze_score_pmfs<S: InternalScorer>(
-    scorer: &S,
-    blend_data: bool,
+fn analyze_score_pmfs(
+    score_pmfs: Vec<Vec<(u16, f64)>>,
+    pmf_sum_tol: f64,
 ) -> Result<ScorePmfAnalysis, UpgradePolicySolverError> {
-    let score_pmfs = scorer.build_score_pmfs(blend_data);
     if score_pmfs.len() != NUM_BUFFS {
         return Err(UpgradePolicySolverError::InvalidScorePmfCount {
             count: score_pmfs.len(),
@@ -80,7 +113,6 @@ fn analyze_score_pmfs<S: InternalScorer>(
     let mut buff_max_score = [0u16; NUM_BUFFS];
     let mut pmf_len = [0usize; NUM_BUFFS];
     let mut top_max_scores = [0u16; NUM_ECHO_SLOTS];
-    const PMF_SUM_TOL: f64 = 1e-9;
 
     for buff_index in 0..NUM_BUFFS {
         let buff_pmf = &score_pmfs[buff_index];
@@ -102,7 +134,7 @@ fn analyze_score_pmfs<S: InternalScorer>(
             }
             probability_sum += probability;
         }
-        if (probability_sum - 1.0).abs() > PMF_SUM_TOL {
+        if (probability_sum - 1.0).abs() > pmf_sum_tol {
             return Err(UpgradePolicySolverError::InvalidScorePmfNotNormalized {
                 buff_index,
                 probability_sum,
@@ -171,12 +203,22 @@ impl MaskCache {
         self.cut_off_score.map(|s| score >= s)
     }
 
-    /// This do not check whether `score` is valid.
+    /// This do not check whether `score` is valid (except in debug builds).
     /// If the input `score` is not valid, the resulting index is out of bound!
     fn score_to_index(&self, score: u16) -> usize {
+        debug_assert!(
+            score >= self.min_score && ((score - self.min_score) as usize) < self.dp.len(),
+            "MaskCache::score_to_index: score {score} outside [{}, {}]",
+            self.min_score,
+            self.min_score as usize + self.dp.len() - 1
+        );
         (score - self.min_score) as usize
     }
 
+    fn contains_score(&self, score: u16) -> bool {
+        score >= self.min_score && ((score - self.min_score) as usize) < self.dp.len()
+    }
+
     /// Get the dp value for a score.
     ///
     /// Output is NAN if the dp value has not been set.
@@ -208,6 +250,16 @@ pub struct ExpectedUpgradeCost {
     success_probability: f64,
     tuner_per_success: f64,
     exp_per_success: f64,
+    // The refunded part paid back to keep the successful echo, included above.
+    keep_tuner: f64,
+    keep_exp: f64,
+    tuner_exp_covariance: f64,
+    reveals_per_attempt: f64,
+    // Weighted cost of one started echo (excluding the refunded part of a kept echo).
+    attempt_cost_mean: f64,
+    attempt_cost_variance: f64,
+    success_additional_cost: f64,
+    shell_credits_per_success: f64,
 }
 
 impl ExpectedUpgradeCost {
@@ -226,6 +278,341 @@ impl ExpectedUpgradeCost {
     pub fn exp_per_success(&self) -> f64 {
         self.exp_per_success
     }
+
+    /// Shell credits paid per success, from the gross tune attempts and raw EXP fed.
+    ///
+    /// Refunds do not give shell credits back, so this counts every reveal of every echo.
+    pub fn shell_credits_per_success(&self) -> f64 {
+        self.shell_credits_per_success
+    }
+
+    /// Tuner and exp per success blended at an ad-hoc exchange rate, without re-solving.
+    ///
+    /// Unlike `weighted_expected_cost`, the echo cost is left out and the policy stays the
+    /// one derived under the `CostModel` weights.
+    pub fn combined_resource(&self, tuner_weight: f64, exp_weight: f64) -> f64 {
+        tuner_weight * self.tuner_per_success + exp_weight * self.exp_per_success
+    }
+
+    /// The covariance between tuner and exp spent on one started echo
+    /// (excluding the refunded part paid back by a kept echo).
+    pub fn tuner_exp_covariance(&self) -> f64 {
+        self.tuner_exp_covariance
+    }
+
+    /// The expected number of slots revealed on one started echo.
+    pub fn expected_reveals_per_attempt(&self) -> f64 {
+        self.reveals_per_attempt
+    }
+
+    pub fn expected_reveals_per_success(&self) -> f64 {
+        self.reveals_per_attempt / self.success_probability
+    }
+
+    pub fn successes_per_hundred_echoes(&self) -> f64 {
+        100.0 * self.success_probability
+    }
+
+    /// `echo_per_success` rounded to the nearest whole echo.
+    pub fn echo_per_success_rounded(&self) -> u64 {
+        self.echo_per_success().round() as u64
+    }
+
+    /// Mean and variance of the weighted cost spent until the first success.
+    ///
+    /// The number of echoes is geometric in `success_probability` and each echo's cost is
+    /// treated as independent of whether it succeeds, so the variance is an approximation.
+    pub fn single_campaign_cost(&self) -> (f64, f64) {
+        let p = self.success_probability;
+        let mu = self.attempt_cost_mean;
+        let mean = mu / p + self.success_additional_cost;
+        let variance = self.attempt_cost_variance / p + mu * mu * (1.0 - p) / (p * p);
+        (mean, variance)
+    }
+
+    /// The expected echoes to get `pieces` successes, e.g. 5 for a full set.
+    pub fn echoes_for_full_set(&self, pieces: usize) -> f64 {
+        pieces as f64 * self.echo_per_success()
+    }
+
+    /// The variance of `echoes_for_full_set`, a sum of `pieces` independent geometrics.
+    pub fn echoes_for_full_set_variance(&self, pieces: usize) -> f64 {
+        let p = self.success_probability;
+        pieces as f64 * (1.0 - p) / (p * p)
+    }
+
+    pub fn tuner_for_full_set(&self, pieces: usize) -> f64 {
+        pieces as f64 * self.tuner_per_success
+    }
+
+    pub fn exp_for_full_set(&self, pieces: usize) -> f64 {
+        pieces as f64 * self.exp_per_success
+    }
+
+    /// Split tuner and exp per success into revealing echoes and keeping the successful one.
+    pub fn cost_breakdown(&self) -> CostBreakdown {
+        CostBreakdown {
+            reveal_tuner: self.tuner_per_success - self.keep_tuner,
+            reveal_exp: self.exp_per_success - self.keep_exp,
+            keep_tuner: self.keep_tuner,
+            keep_exp: self.keep_exp,
+        }
+    }
+
+    /// The expected outcome of farming 100 echoes under the policy.
+    pub fn farming_estimate(&self) -> FarmingEstimate {
+        let successes = self.successes_per_hundred_echoes();
+        FarmingEstimate {
+            successes,
+            tuner: successes * self.tuner_per_success,
+            exp: successes * self.exp_per_success,
+        }
+    }
+}
+
+/// The same target solved under two scorers, see `UpgradePolicySolver::compare_scorers`.
+#[derive(Debug, Clone, Copy)]
+pub struct ScorerComparison {
+    linear_expected_cost: f64,
+    linear_success_probability: f64,
+    fixed_expected_cost: f64,
+    fixed_success_probability: f64,
+}
+
+impl ScorerComparison {
+    pub fn linear_expected_cost(&self) -> f64 {
+        self.linear_expected_cost
+    }
+
+    pub fn linear_success_probability(&self) -> f64 {
+        self.linear_success_probability
+    }
+
+    pub fn fixed_expected_cost(&self) -> f64 {
+        self.fixed_expected_cost
+    }
+
+    pub fn fixed_success_probability(&self) -> f64 {
+        self.fixed_success_probability
+    }
+}
+
+/// Tuner and exp per success, see `ExpectedUpgradeCost::cost_breakdown`.
+#[derive(Debug, Clone, Copy)]
+pub struct CostBreakdown {
+    reveal_tuner: f64,
+    reveal_exp: f64,
+    keep_tuner: f64,
+    keep_exp: f64,
+}
+
+impl CostBreakdown {
+    /// Spent (after refunds) revealing slots on all echoes started per success.
+    pub fn reveal_tuner(&self) -> f64 {
+        self.reveal_tuner
+    }
+
+    pub fn reveal_exp(&self) -> f64 {
+        self.reveal_exp
+    }
+
+    /// The refund paid back to keep the successful echo instead of discarding it.
+    pub fn keep_tuner(&self) -> f64 {
+        self.keep_tuner
+    }
+
+    pub fn keep_exp(&self) -> f64 {
+        self.keep_exp
+    }
+}
+
+/// Expected results per 100 farmed echoes.
+#[derive(Debug, Clone, Copy)]
+pub struct FarmingEstimate {
+    successes: f64,
+    tuner: f64,
+    exp: f64,
+}
+
+impl FarmingEstimate {
+    pub fn successes(&self) -> f64 {
+        self.successes
+    }
+
+    pub fn tuner(&self) -> f64 {
+        self.tuner
+    }
+
+    pub fn exp(&self) -> f64 {
+        self.exp
+    }
+}
+
+/// Wall-clock time spent in the expensive solver stages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    pmf_construction: Duration,
+    lambda_search: Option<Duration>,
+    expected_resources: Option<Duration>,
+}
+
+impl Timings {
+    /// Time spent building and validating the score PMFs in `UpgradePolicySolver::new`.
+    pub fn pmf_construction(&self) -> Duration {
+        self.pmf_construction
+    }
+
+    /// Time spent in the last `lambda_search`, if any.
+    pub fn lambda_search(&self) -> Option<Duration> {
+        self.lambda_search
+    }
+
+    /// Time spent in the last `calculate_expected_resources`, if any.
+    pub fn expected_resources(&self) -> Option<Duration> {
+        self.expected_resources
+    }
+}
+
+/// Sizes of the solver caches, for memory profiling.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheStats {
+    allocated_dp_slots: usize,
+    touched_dp_slots: usize,
+    expected_cost_states: usize,
+}
+
+impl CacheStats {
+    /// Total dp slots allocated across all mask caches.
+    pub fn allocated_dp_slots(&self) -> usize {
+        self.allocated_dp_slots
+    }
+
+    /// Dp slots written by the last policy derivation.
+    pub fn touched_dp_slots(&self) -> usize {
+        self.touched_dp_slots
+    }
+
+    /// States stored by the last `calculate_expected_resources`, 0 if not computed.
+    pub fn expected_cost_states(&self) -> usize {
+        self.expected_cost_states
+    }
+}
+
+/// The size of the DP a policy derivation walks, which scales with its run time.
+#[derive(Debug, Clone, Copy)]
+pub struct WorkEstimate {
+    partial_masks: usize,
+    score_states: usize,
+}
+
+impl WorkEstimate {
+    pub fn partial_masks(&self) -> usize {
+        self.partial_masks
+    }
+
+    /// Score states summed over all partial masks, i.e. the DP table size.
+    pub fn score_states(&self) -> usize {
+        self.score_states
+    }
+}
+
+/// Every input that determines a solver's results, for attaching to bug reports.
+///
+/// `hash` covers all other fields and the score PMFs, so two solvers with the same hash
+/// produce the same numbers. It is a hex string because JSON numbers lose `u64` precision.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFingerprint {
+    scorer_weights: Option<[f64; NUM_BUFFS]>,
+    blend_data: bool,
+    target_score: u16,
+    score_multiplier: f64,
+    max_reveals: usize,
+    reward_slope: f64,
+    reforge_cost: Option<f64>,
+    cost_weights: (f64, f64, f64),
+    exp_refund_ratio: f64,
+    abandon_refund_ratio: f64,
+    echo_acquisition_cost: f64,
+    buff_reveal_multipliers: [f64; NUM_BUFFS],
+    hash: String,
+}
+
+impl ConfigFingerprint {
+    /// `None` for solvers built from PMFs or from a scorer without per-buff weights.
+    pub fn scorer_weights(&self) -> Option<[f64; NUM_BUFFS]> {
+        self.scorer_weights
+    }
+
+    pub fn blend_data(&self) -> bool {
+        self.blend_data
+    }
+
+    /// The target in internal score units.
+    pub fn target_score(&self) -> u16 {
+        self.target_score
+    }
+
+    pub fn score_multiplier(&self) -> f64 {
+        self.score_multiplier
+    }
+
+    pub fn max_reveals(&self) -> usize {
+        self.max_reveals
+    }
+
+    pub fn reward_slope(&self) -> f64 {
+        self.reward_slope
+    }
+
+    pub fn reforge_cost(&self) -> Option<f64> {
+        self.reforge_cost
+    }
+
+    /// The `(echo, tuner, exp)` cost model weights.
+    pub fn cost_weights(&self) -> (f64, f64, f64) {
+        self.cost_weights
+    }
+
+    pub fn exp_refund_ratio(&self) -> f64 {
+        self.exp_refund_ratio
+    }
+
+    pub fn abandon_refund_ratio(&self) -> f64 {
+        self.abandon_refund_ratio
+    }
+
+    pub fn echo_acquisition_cost(&self) -> f64 {
+        self.echo_acquisition_cost
+    }
+
+    pub fn buff_reveal_multipliers(&self) -> [f64; NUM_BUFFS] {
+        self.buff_reveal_multipliers
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+/// 64-bit FNV-1a, used for fingerprints because it is stable across Rust versions.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_f64(&mut self, value: f64) {
+        self.write(&value.to_bits().to_le_bytes());
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -233,6 +620,14 @@ struct ExpectedUpgradeCostState {
     success_probability: f64,
     tuner: f64,
     exp: f64,
+    // E[tuner * exp]
+    tuner_exp: f64,
+    // E[tuner^2] and E[exp^2]
+    tuner_sq: f64,
+    exp_sq: f64,
+    reveals: f64,
+    // Raw EXP fed before refunds, for shell credits.
+    raw_exp: f64,
 }
 
 impl Default for ExpectedUpgradeCostState {
@@ -241,6 +636,11 @@ impl Default for ExpectedUpgradeCostState {
             success_probability: f64::NAN,
             tuner: 0.0,
             exp: 0.0,
+            tuner_exp: 0.0,
+            tuner_sq: 0.0,
+            exp_sq: 0.0,
+            reveals: 0.0,
+            raw_exp: 0.0,
         }
     }
 }
@@ -251,18 +651,276 @@ impl ExpectedUpgradeCostState {
             success_probability: 0.0,
             tuner: 0.0,
             exp: 0.0,
+            tuner_exp: 0.0,
+            tuner_sq: 0.0,
+            exp_sq: 0.0,
+            reveals: 0.0,
+            raw_exp: 0.0,
+        }
+    }
+
+    /// An echo discarded with `num_filled_slots` revealed, refunding part of what was spent.
+    fn abandoned_state(cost_model: &CostModel, num_filled_slots: usize) -> Self {
+        let ratio = cost_model.abandon_refund_ratio();
+        if ratio == 0.0 || num_filled_slots == 0 {
+            return Self::failed_state();
+        }
+        let tuner = -ratio * num_filled_slots as f64 * cost_model.tuner_cost();
+        let exp = -ratio * cost_model.upgrade_exp_cost(0, num_filled_slots);
+
+        Self {
+            success_probability: 0.0,
+            tuner,
+            exp,
+            tuner_exp: tuner * exp,
+            tuner_sq: tuner * tuner,
+            exp_sq: exp * exp,
+            reveals: 0.0,
+            raw_exp: 0.0,
         }
     }
 
-    fn guaranteed_success_state(cost_model: &CostModel, num_filled_slots: usize) -> Self {
-        let tuner = (NUM_ECHO_SLOTS - num_filled_slots) as f64 * cost_model.tuner_cost();
-        let exp = cost_model.full_upgrade_exp_cost(num_filled_slots);
+    fn guaranteed_success_state(
+        cost_model: &CostModel,
+        num_filled_slots: usize,
+        max_reveals: usize,
+    ) -> Self {
+        let tuner = (max_reveals - num_filled_slots) as f64 * cost_model.tuner_cost();
+        let exp = cost_model.upgrade_exp_cost(num_filled_slots, max_reveals);
 
         Self {
             success_probability: 1.0,
             tuner,
             exp,
+            tuner_exp: tuner * exp,
+            tuner_sq: tuner * tuner,
+            exp_sq: exp * exp,
+            reveals: (max_reveals - num_filled_slots) as f64,
+            raw_exp: cost_model.raw_upgrade_exp(num_filled_slots, max_reveals),
+        }
+    }
+
+    /// Add `probability` times `state`, for averaging over the next reveal.
+    fn accumulate(&mut self, probability: f64, state: &Self) {
+        self.success_probability += probability * state.success_probability;
+        self.tuner += probability * state.tuner;
+        self.exp += probability * state.exp;
+        self.tuner_exp += probability * state.tuner_exp;
+        self.tuner_sq += probability * state.tuner_sq;
+        self.exp_sq += probability * state.exp_sq;
+        self.reveals += probability * state.reveals;
+        self.raw_exp += probability * state.raw_exp;
+    }
+
+    fn scale(&mut self, factor: f64) {
+        self.success_probability *= factor;
+        self.tuner *= factor;
+        self.exp *= factor;
+        self.tuner_exp *= factor;
+        self.tuner_sq *= factor;
+        self.exp_sq *= factor;
+        self.reveals *= factor;
+        self.raw_exp *= factor;
+    }
+
+    /// Add the deterministic cost of the reveal made before reaching this state.
+    fn add_step_cost(&mut self, tuner: f64, exp: f64, raw_exp: f64) {
+        self.raw_exp += raw_exp;
+        self.tuner_exp += tuner * exp + tuner * self.exp + exp * self.tuner;
+        self.tuner_sq += tuner * tuner + 2.0 * tuner * self.tuner;
+        self.exp_sq += exp * exp + 2.0 * exp * self.exp;
+        self.tuner += tuner;
+        self.exp += exp;
+        self.reveals += 1.0;
+    }
+}
+
+type FirstOfScores = [u16; MAX_FIRST_OF_TARGETS];
+
+/// The value DP behind `UpgradePolicySolver::first_of`.
+///
+/// Scores are clamped to their targets, so a state is success-bound as soon as any clamped
+/// score equals its target, and unused target slots stay at 0.
+struct FirstOfSolver {
+    // Per buff, the scores of one rolled value under every scorer and their probability.
+    joint_pmfs: Vec<Vec<(FirstOfScores, f64)>>,
+    targets: FirstOfScores,
+    num_targets: usize,
+    buff_max_scores: [[u16; NUM_BUFFS]; MAX_FIRST_OF_TARGETS],
+    cost_model: CostModel,
+    max_lambda_expansions: usize,
+    lambda: f64,
+    memo: HashMap<(u16, FirstOfScores), f64>,
+}
+
+impl FirstOfSolver {
+    fn new(
+        scorers_targets: &[(&dyn InternalScorer, f64)],
+        blend_data: bool,
+        cost_model: CostModel,
+    ) -> Result<Self, UpgradePolicySolverError> {
+        let num_targets = scorers_targets.len();
+        if !(1..=MAX_FIRST_OF_TARGETS).contains(&num_targets) {
+            return Err(UpgradePolicySolverError::InvalidTargetCount { count: num_targets });
+        }
+
+        let value_pmfs = build_value_pmfs(blend_data);
+        let mut targets = [0u16; MAX_FIRST_OF_TARGETS];
+        let mut buff_max_scores = [[0u16; NUM_BUFFS]; MAX_FIRST_OF_TARGETS];
+        let mut joint_pmfs: Vec<Vec<(FirstOfScores, f64)>> = value_pmfs
+            .iter()
+            .map(|pmf| vec![([0u16; MAX_FIRST_OF_TARGETS], 0.0); pmf.len()])
+            .collect();
+        for (k, &(scorer, target_score_display)) in scorers_targets.iter().enumerate() {
+            targets[k] = normalize_target_score(target_score_display, scorer.score_multiplier())?;
+            for (buff_index, pmf) in value_pmfs.iter().enumerate() {
+                for (entry, &(buff_value, probability)) in
+                    joint_pmfs[buff_index].iter_mut().zip(pmf.iter())
+                {
+                    let score = scorer
+                        .buff_score_internal(buff_index, buff_value)
+                        .map_err(|error| UpgradePolicySolverError::InvalidScorer { error })?;
+                    entry.0[k] = score;
+                    entry.1 = probability;
+                    buff_max_scores[k][buff_index] = buff_max_scores[k][buff_index].max(score);
+                }
+            }
+            let mut top_max_scores = buff_max_scores[k];
+            top_max_scores.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+            let max_score_sum: u32 = top_max_scores[..NUM_ECHO_SLOTS]
+                .iter()
+                .map(|&score| u32::from(score))
+                .sum();
+            if max_score_sum > u16::MAX as u32 {
+                return Err(UpgradePolicySolverError::ScoreRangeOverflow { max_score_sum });
+            }
+            validate_target_score(targets[k], max_score_sum as u16)?;
+        }
+
+        Ok(Self {
+            joint_pmfs,
+            targets,
+            num_targets,
+            buff_max_scores,
+            cost_model,
+            max_lambda_expansions: DEFAULT_MAX_LAMBDA_EXPANSIONS,
+            lambda: 0.0,
+            memo: HashMap::new(),
+        })
+    }
+
+    /// Bisect the lambda where the root is worth starting, like `lambda_search`.
+    fn lambda_search(
+        &mut self,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        if tol.is_nan() || tol.is_infinite() || tol <= 0.0 {
+            return Err(UpgradePolicySolverError::InvalidTolerance { tolerance: tol });
+        }
+
+        let mut a = 0.0;
+        let mut b = 1.0;
+        if self.root_advantage(a) < 0.0 {
+            return Err(UpgradePolicySolverError::LambdaNotBracketed);
+        }
+        let mut fb = self.root_advantage(b);
+        let mut expand_count: usize = 0;
+        while fb > 0.0 {
+            if expand_count >= self.max_lambda_expansions {
+                return Err(UpgradePolicySolverError::LambdaBracketExpansionExhausted {
+                    attempts: expand_count,
+                });
+            }
+            a = b;
+            b *= 2.0;
+            fb = self.root_advantage(b);
+            expand_count += 1;
+        }
+
+        let mut last_candidate = b;
+        let mut last_advantage = fb;
+        for _ in 0..max_iter {
+            let c = 0.5 * (a + b);
+            let fc = self.root_advantage(c);
+            if fc.abs() <= tol || (b - a).abs() <= tol * (1.0 + c.abs()) {
+                return Ok(c);
+            }
+            if fc > 0.0 {
+                a = c;
+            } else {
+                b = c;
+            }
+            last_candidate = c;
+            last_advantage = fc;
+        }
+        Err(UpgradePolicySolverError::LambdaNotFoundWithinMaxIter {
+            last_candidate,
+            last_advantage,
+            bracket: (a, b),
+        })
+    }
+
+    fn root_advantage(&mut self, lambda: f64) -> f64 {
+        self.lambda = lambda;
+        self.memo.clear();
+        self.continue_advantage(0u16, [0u16; MAX_FIRST_OF_TARGETS])
+            - lambda * self.cost_model.echo_acquisition_cost()
+    }
+
+    fn continue_advantage(&mut self, mask: u16, scores: FirstOfScores) -> f64 {
+        let num_filled_slots = calculate_num_filled_slots(mask);
+        let num_remaining_buffs = NUM_BUFFS - num_filled_slots;
+        let mut total: f64 = 0.0;
+        let mut multiplier_total: f64 = 0.0;
+        let mut remaining_buffs = MASK_ALL ^ mask;
+        while remaining_buffs != 0 {
+            let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
+            let idx = lsb.trailing_zeros() as usize;
+            remaining_buffs ^= lsb;
+            let next_mask = mask | (1u16 << idx);
+            multiplier_total += self.cost_model.buff_reveal_multiplier(idx);
+
+            for j in 0..self.joint_pmfs[idx].len() {
+                let (deltas, probability) = self.joint_pmfs[idx][j];
+                let mut next_scores = scores;
+                for k in 0..self.num_targets {
+                    next_scores[k] = scores[k].saturating_add(deltas[k]).min(self.targets[k]);
+                }
+                total += probability * self.value_rec(next_mask, next_scores);
+            }
+        }
+
+        // The reveal is paid before the buff is known, so it costs the mean multiplier.
+        let expected = total / (num_remaining_buffs as f64);
+        let reveal_cost = self.cost_model.weighted_reveal_cost(num_filled_slots) * multiplier_total
+            / num_remaining_buffs as f64;
+        expected - self.lambda * reveal_cost
+    }
+
+    fn value_rec(&mut self, mask: u16, scores: FirstOfScores) -> f64 {
+        let reached = (0..self.num_targets).any(|k| scores[k] >= self.targets[k]);
+        if calculate_num_filled_slots(mask) >= NUM_ECHO_SLOTS {
+            return if reached { DP_VALUE_MULTIPLIER } else { 0.0 };
+        }
+        // Once a target is reached only the mask matters.
+        let scores = if reached { self.targets } else { scores };
+
+        if let Some(&dp) = self.memo.get(&(mask, scores)) {
+            return dp;
         }
+        let reachable = (0..self.num_targets).any(|k| {
+            u32::from(scores[k])
+                + u32::from(best_case_remaining_score(mask, &self.buff_max_scores[k]))
+                >= u32::from(self.targets[k])
+        });
+        let dp = if reachable {
+            self.continue_advantage(mask, scores).max(0.0)
+        } else {
+            0.0
+        };
+        self.memo.insert((mask, scores), dp);
+        dp
     }
 }
 
@@ -307,26 +965,195 @@ pub enum UpgradePolicySolverError {
         tolerance: f64,
     },
     LambdaNotBracketed,
-    LambdaNotFoundWithinMaxIter,
+    LambdaBracketExpansionExhausted {
+        attempts: usize,
+    },
+    LambdaNotFoundWithinMaxIter {
+        last_candidate: f64,
+        last_advantage: f64,
+        bracket: (f64, f64),
+    },
     PolicyNotDerived,
     TargetScoreImpossible {
         max_possible_score: u16,
         target_score: u16,
     },
+    TargetScoreOutOfRange {
+        target_score_display: f64,
+    },
+    InvalidRewardSlope {
+        slope: f64,
+    },
+    InvalidReforgeCost {
+        cost: f64,
+    },
+    InvalidSuccessProbability {
+        probability: f64,
+    },
+    InvalidEpsilon {
+        epsilon: f64,
+    },
+    InvalidScorer {
+        error: ScorerError,
+    },
+    InvalidCostModel {
+        error: CostModelError,
+    },
+    InvalidMaxReveals {
+        max_reveals: usize,
+    },
+    InvalidBudget {
+        budget: f64,
+    },
+    BudgetTooSmall {
+        budget: f64,
+        min_cost: f64,
+    },
+    InvalidTargetCount {
+        count: usize,
+    },
+    NoImprovingWeightAdjustment,
+    InvalidTargetDelta {
+        delta: f64,
+    },
+    ThresholdsNeverSucceed,
+    PolicyNeverSucceeds,
+    ScorerMismatch,
 }
 
-pub struct UpgradePolicySolver {
-    score_pmfs: Vec<Vec<(u16, f64)>>,
+impl std::fmt::Display for UpgradePolicySolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ExpectedResourcesNotComputed => {
+                write!(f, "expected resources have not been computed")
+            }
+            Self::InvalidMask { mask } => write!(f, "invalid mask {mask:#06x}"),
+            Self::InvalidScorePmfCount { count } => {
+                write!(f, "expected {NUM_BUFFS} score PMFs, got {count}")
+            }
+            Self::InvalidScorePmfEmpty { buff_index } => {
+                write!(f, "score PMF of buff {buff_index} is empty")
+            }
+            Self::InvalidScorePmfProbability {
+                buff_index,
+                probability,
+            } => {
+                write!(
+                    f,
+                    "score PMF of buff {buff_index} has invalid probability {probability}"
+                )
+            }
+            Self::InvalidScorePmfNotNormalized {
+                buff_index,
+                probability_sum,
+            } => write!(
+                f,
+                "score PMF of buff {buff_index} sums to {probability_sum} instead of 1"
+            ),
+            Self::ScoreRangeOverflow { max_score_sum } => {
+                write!(
+                    f,
+                    "maximum score sum {max_score_sum} overflows the score range"
+                )
+            }
+            Self::InvalidScore => write!(f, "invalid score"),
+            Self::InvalidTolerance { tolerance } => {
+                write!(f, "tolerance must be a positive number, got {tolerance}")
+            }
+            Self::LambdaNotBracketed => write!(f, "failed to bracket lambda"),
+            Self::LambdaBracketExpansionExhausted { attempts } => {
+                write!(f, "failed to bracket lambda after {attempts} expansions")
+            }
+            Self::LambdaNotFoundWithinMaxIter {
+                last_candidate,
+                last_advantage,
+                bracket,
+            } => {
+                write!(
+                    f,
+                    "lambda not found within the maximum number of iterations \
+                     (last candidate {last_candidate}, advantage {last_advantage}, \
+                     bracket [{}, {}])",
+                    bracket.0, bracket.1
+                )
+            }
+            Self::PolicyNotDerived => write!(f, "policy has not been derived"),
+            Self::TargetScoreImpossible {
+                max_possible_score,
+                target_score,
+            } => write!(
+                f,
+                "target score {target_score} exceeds the maximum possible score \
+                 {max_possible_score}"
+            ),
+            Self::TargetScoreOutOfRange {
+                target_score_display,
+            } => {
+                write!(f, "target score {target_score_display} is out of range")
+            }
+            Self::InvalidRewardSlope { slope } => write!(f, "invalid reward slope {slope}"),
+            Self::InvalidReforgeCost { cost } => write!(f, "invalid reforge cost {cost}"),
+            Self::InvalidSuccessProbability { probability } => {
+                write!(f, "invalid success probability {probability}")
+            }
+            Self::InvalidEpsilon { epsilon } => write!(f, "invalid epsilon {epsilon}"),
+            Self::InvalidScorer { error } => write!(f, "invalid scorer: {error}"),
+            Self::InvalidCostModel { error } => write!(f, "invalid cost model: {error}"),
+            Self::InvalidMaxReveals { max_reveals } => {
+                write!(f, "invalid number of reveals {max_reveals}")
+            }
+            Self::InvalidBudget { budget } => write!(f, "invalid budget {budget}"),
+            Self::BudgetTooSmall { budget, min_cost } => {
+                write!(f, "budget {budget} is below the minimum cost {min_cost}")
+            }
+            Self::InvalidTargetCount { count } => write!(f, "invalid target count {count}"),
+            Self::NoImprovingWeightAdjustment => {
+                write!(f, "no weight adjustment improves the expected cost")
+            }
+            Self::InvalidTargetDelta { delta } => write!(f, "invalid target delta {delta}"),
+            Self::ThresholdsNeverSucceed => write!(f, "the thresholds never succeed"),
+            Self::PolicyNeverSucceeds => write!(f, "the policy never succeeds"),
+            Self::ScorerMismatch => {
+                write!(f, "the scorer does not reproduce the solver's score PMFs")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UpgradePolicySolverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidScorer { error } => Some(error),
+            Self::InvalidCostModel { error } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+pub struct UpgradePolicySolver {
+    score_pmfs: Vec<Vec<(u16, f64)>>,
     target_score: u16,
     cost_model: CostModel,
     lambda: f64,
     is_policy_derived: bool,
+    reward_slope: f64,
+    reforge_cost: Option<f64>,
+    blend_data: bool,
+    scorer_weights: Option<[f64; NUM_BUFFS]>,
+    max_reveals: usize,
+    max_lambda_expansions: usize,
 
     pmf_len: [usize; NUM_BUFFS],
+    buff_min_score: [u16; NUM_BUFFS],
+    buff_max_score: [u16; NUM_BUFFS],
     max_possible_score: u16,
+    score_multiplier: f64,
     caches: Vec<MaskCache>,
     touched_cache: Vec<usize>,
     expected_cost_cache: ExpectedCostCache,
+
+    timing_enabled: bool,
+    timings: Timings,
 }
 
 impl UpgradePolicySolver {
@@ -338,43 +1165,250 @@ impl UpgradePolicySolver {
         self.is_policy_derived
     }
 
-    pub fn get_decision(&self, mask: u16, score: u16) -> Result<bool, UpgradePolicySolverError> {
+    /// The lambda the current policy was derived at, `None` before one is derived.
+    pub fn lambda(&self) -> Option<f64> {
+        self.is_policy_derived.then_some(self.lambda)
+    }
+
+    /// Internal score units per display score unit, taken from the scorer.
+    pub fn score_multiplier(&self) -> f64 {
+        self.score_multiplier
+    }
+
+    /// The largest target every fully revealed echo reaches, i.e. the lowest possible
+    /// score of an echo with `max_reveals` slots. Targets up to this succeed with certainty.
+    pub fn guaranteed_target(&self) -> u16 {
+        let mut buff_min_score = self.buff_min_score;
+        buff_min_score.sort_unstable();
+        buff_min_score[..self.max_reveals].iter().sum()
+    }
+
+    /// The `(min, max)` internal score of each buff, as used to size the solver caches.
+    pub fn buff_score_bounds(&self) -> [(u16, u16); NUM_BUFFS] {
+        std::array::from_fn(|buff_index| {
+            (
+                self.buff_min_score[buff_index],
+                self.buff_max_score[buff_index],
+            )
+        })
+    }
+
+    /// The fewest buffs from the `good` buff mask that reach the target at their mean scores.
+    ///
+    /// Fails with `TargetScoreImpossible` if even `max_reveals` of them fall short.
+    pub fn min_good_substats(&self, good: u16) -> Result<usize, UpgradePolicySolverError> {
+        if good == 0 || (good & !MASK_ALL) != 0 {
+            return Err(UpgradePolicySolverError::InvalidMask { mask: good });
+        }
+
+        let mut mean_scores: Vec<f64> = (0..NUM_BUFFS)
+            .filter(|&buff_index| (good & (1u16 << buff_index)) != 0)
+            .map(|buff_index| {
+                self.score_pmfs[buff_index]
+                    .iter()
+                    .map(|&(score, probability)| score as f64 * probability)
+                    .sum()
+            })
+            .collect();
+        mean_scores.sort_unstable_by(|lhs, rhs| rhs.total_cmp(lhs));
+
+        if self.target_score == 0 {
+            return Ok(0);
+        }
+        let mut total: f64 = 0.0;
+        for (count, &mean_score) in mean_scores.iter().take(self.max_reveals).enumerate() {
+            total += mean_score;
+            if total >= self.target_score as f64 {
+                return Ok(count + 1);
+            }
+        }
+        Err(UpgradePolicySolverError::TargetScoreImpossible {
+            max_possible_score: total.floor() as u16,
+            target_score: self.target_score,
+        })
+    }
+
+    /// How many times `lambda_search` may double the upper bracket (80 by default).
+    pub fn set_max_lambda_expansions(&mut self, max_expansions: usize) {
+        self.max_lambda_expansions = max_expansions;
+    }
+
+    /// The timing breakdown of the last solver run, if enabled by `with_timing`.
+    pub fn last_timings(&self) -> Option<Timings> {
+        self.timing_enabled.then_some(self.timings)
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        let allocated_dp_slots = self.caches.iter().map(|cache| cache.dp.len()).sum();
+        let touched_dp_slots = self
+            .touched_cache
+            .iter()
+            .map(|&index| self.caches[index].touched.len())
+            .sum();
+        let expected_cost_states = match &self.expected_cost_cache {
+            ExpectedCostCache::NotComputed => 0,
+            ExpectedCostCache::Computed(memo) => memo
+                .iter()
+                .map(|entry| match entry {
+                    ExpectedCostCacheEntry::Abandon => 0,
+                    ExpectedCostCacheEntry::Reachable { states, .. } => states.len(),
+                })
+                .sum(),
+        };
+        CacheStats {
+            allocated_dp_slots,
+            touched_dp_slots,
+            expected_cost_states,
+        }
+    }
+
+    /// The DP table size set up in `new`, for warning about slow derivations up front.
+    ///
+    /// Known before `lambda_search`; each of its iterations walks at most this many states.
+    pub fn estimated_work(&self) -> WorkEstimate {
+        WorkEstimate {
+            partial_masks: self.caches.len(),
+            score_states: self.caches.iter().map(|cache| cache.dp.len()).sum(),
+        }
+    }
+
+    /// Whether to continue upgrading. A full mask has nothing left to reveal and never continues.
+    pub fn get_decision(
+        &self,
+        mask: impl Into<EchoMask>,
+        score: RawScore,
+    ) -> Result<bool, UpgradePolicySolverError> {
+        let score = score.into_raw();
         if !self.is_policy_derived() {
             return Err(UpgradePolicySolverError::PolicyNotDerived);
         }
 
-        if is_valid_external_partial_mask(mask) {
-            if mask == 0 {
-                return Ok(true);
+        match mask.into() {
+            EchoMask::Partial(mask) => {
+                let mask = mask.bits();
+                if mask == 0 {
+                    return Ok(true);
+                }
+                let cache_index = partial_mask_to_index(mask);
+                Ok(self.caches[cache_index]
+                    .get_decision(score)
+                    .unwrap_or(false))
             }
-            let cache_index = partial_mask_to_index(mask);
-            return Ok(self.caches[cache_index]
-                .get_decision(score)
-                .unwrap_or(false));
+            EchoMask::Full(_) => Ok(false),
         }
+    }
 
-        if is_valid_external_full_mask(mask) {
-            return Ok(false);
+    /// `get_decision` with a display score, rounded to the internal score.
+    pub fn get_decision_display(
+        &self,
+        mask: impl Into<EchoMask>,
+        score_display: f64,
+    ) -> Result<bool, UpgradePolicySolverError> {
+        self.get_decision(
+            mask,
+            RawScore::new(display_score_to_internal(
+                score_display,
+                self.score_multiplier,
+            )?),
+        )
+    }
+
+    /// The minimum score to continue for every partial mask, aligned to `partial_mask_at`.
+    ///
+    /// `None` means the mask is always abandoned. The empty mask always continues.
+    pub fn cut_off_scores(&self) -> Result<Vec<Option<u16>>, UpgradePolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(UpgradePolicySolverError::PolicyNotDerived);
         }
 
-        Err(UpgradePolicySolverError::InvalidMask { mask })
+        let mut cut_off_scores: Vec<Option<u16>> = self
+            .caches
+            .iter()
+            .map(|cache| cache.cut_off_score)
+            .collect();
+        cut_off_scores[partial_mask_to_index(0u16)] = Some(0);
+        Ok(cut_off_scores)
+    }
+
+    /// The decisions aggregated by number of filled slots, for plotting the continue region.
+    ///
+    /// Row `k` is for `k + 1` filled slots (1 to 4) and column `s` is for internal score `s`
+    /// in `0..=target_score`. A cell is `true` (Continue) if any mask with that many filled
+    /// slots continues at that score, i.e. the score reaches the smallest cut off score among
+    /// those masks.
+    pub fn decision_grid(&self) -> Result<Vec<Vec<bool>>, UpgradePolicySolverError> {
+        let threshold_curve = self.threshold_curve()?;
+        Ok(threshold_curve[1..]
+            .iter()
+            .map(|min_cut_off_score| {
+                (0..=self.target_score)
+                    .map(|score| min_cut_off_score.is_some_and(|s| score >= s))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// The smallest cut off score among masks with `k` filled slots, for `k` in 0 to 4.
+    ///
+    /// An echo at depth `k` is worth continuing only if its score reaches entry `k`, and
+    /// `None` means no mask at that depth continues. The root (`k = 0`) always continues.
+    pub fn threshold_curve(
+        &self,
+    ) -> Result<[Option<u16>; NUM_ECHO_SLOTS], UpgradePolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(UpgradePolicySolverError::PolicyNotDerived);
+        }
+
+        let mut min_cut_off_scores: [Option<u16>; NUM_ECHO_SLOTS] = [None; NUM_ECHO_SLOTS];
+        min_cut_off_scores[0] = Some(0);
+        for (&mask, cache) in PARTIAL_MASKS.iter().zip(self.caches.iter()) {
+            let num_filled_slots = calculate_num_filled_slots(mask);
+            if num_filled_slots == 0 {
+                continue;
+            }
+            if let Some(cut_off_score) = cache.cut_off_score {
+                let min_cut_off_score = &mut min_cut_off_scores[num_filled_slots];
+                *min_cut_off_score =
+                    Some(min_cut_off_score.map_or(cut_off_score, |s| s.min(cut_off_score)));
+            }
+        }
+        Ok(min_cut_off_scores)
+    }
+
+    /// The score distribution of a fully revealed echo when nothing is abandoned.
+    ///
+    /// Every set of `max_reveals` buffs is equally likely, so this averages the convolved
+    /// score PMFs over all of them. Entries are `(internal score, probability)`, ascending.
+    pub fn raw_score_distribution(&self) -> Vec<(u16, f64)> {
+        let len = self.max_possible_score as usize + 1;
+        let mut start = vec![0.0; len];
+        start[0] = 1.0;
+        let mut total = vec![0.0; len];
+        let mut num_buff_sets: usize = 0;
+        self.accumulate_raw_scores(0, 0, &start, &mut total, &mut num_buff_sets);
+
+        total
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, probability)| probability > 0.0)
+            .map(|(score, probability)| (score as u16, probability / num_buff_sets as f64))
+            .collect()
     }
 
     /// This is the probability of reaching target_score by strictly following the policy.
     pub fn get_success_probability(
         &self,
-        mask: u16,
-        score: u16,
+        mask: impl Into<EchoMask>,
+        score: RawScore,
     ) -> Result<f64, UpgradePolicySolverError> {
-        if !is_valid_external_partial_mask(mask) && !is_valid_external_full_mask(mask) {
-            return Err(UpgradePolicySolverError::InvalidMask { mask });
-        }
-        if score >= self.target_score {
+        let mask = mask.into();
+        if score.into_raw() >= self.target_score {
             return Ok(1.0);
         }
         if !self.get_decision(mask, score)? {
             return Ok(0.0);
         }
+        let score = score.into_raw();
 
         let cache = match &self.expected_cost_cache {
             ExpectedCostCache::NotComputed => {
@@ -382,7 +1416,8 @@ impl UpgradePolicySolver {
             }
             ExpectedCostCache::Computed(cache) => cache,
         };
-        let cache_index = partial_mask_to_index(mask);
+        // Only partial masks can continue, see `get_decision`.
+        let cache_index = partial_mask_to_index(mask.bits());
         let probability = match &cache[cache_index] {
             ExpectedCostCacheEntry::Abandon => 0.0,
             ExpectedCostCacheEntry::Reachable {
@@ -407,29 +1442,572 @@ impl UpgradePolicySolver {
         Ok(probability)
     }
 
+    /// `get_success_probability` with a display score, rounded to the internal score.
+    pub fn get_success_probability_display(
+        &self,
+        mask: impl Into<EchoMask>,
+        score_display: f64,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        self.get_success_probability(
+            mask,
+            RawScore::new(display_score_to_internal(
+                score_display,
+                self.score_multiplier,
+            )?),
+        )
+    }
+
+    /// The probability that revealing the last slot of a 4-slot `mask` at `score` reaches the
+    /// target, averaged over the unrevealed buffs and their values.
+    ///
+    /// Independent of the policy, so it also works before one is derived. Fails with
+    /// `InvalidMask` if the solver stops before the fifth reveal.
+    pub fn last_reveal_success_probability(
+        &self,
+        mask: impl Into<EchoMask>,
+        score: RawScore,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        let mask = match mask.into() {
+            EchoMask::Partial(mask)
+                if calculate_num_filled_slots(mask.bits()) == NUM_ECHO_SLOTS - 1
+                    && self.max_reveals == NUM_ECHO_SLOTS =>
+            {
+                mask.bits()
+            }
+            mask => return Err(UpgradePolicySolverError::InvalidMask { mask: mask.bits() }),
+        };
+        let score = score.into_raw();
+        if score >= self.target_score {
+            return Ok(1.0);
+        }
+
+        let num_remaining_buffs = NUM_BUFFS - (NUM_ECHO_SLOTS - 1);
+        let mut total: f64 = 0.0;
+        let mut remaining_buffs = MASK_ALL ^ mask;
+        while remaining_buffs != 0 {
+            let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
+            let index = lsb.trailing_zeros() as usize;
+            remaining_buffs ^= lsb;
+
+            for j in 0..self.pmf_len[index] {
+                let (delta, probability) = self.score_pmf_entry(index, j);
+                if score.saturating_add(delta) >= self.target_score {
+                    total += probability;
+                }
+            }
+        }
+        Ok(total / num_remaining_buffs as f64)
+    }
+
+    /// The expected extra weighted cost to reach a success caused by choosing `chose_continue`
+    /// at `(mask, score)` instead of the policy's decision (0.0 if they agree).
+    ///
+    /// Continuing from a state changes its value by `advantage` (in reward units), and one
+    /// unit of reward costs `1 / lambda`, so a wrong choice costs `|advantage| / lambda`.
+    pub fn decision_regret(
+        &self,
+        mask: impl Into<EchoMask>,
+        score: RawScore,
+        chose_continue: bool,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        let score = score.into_raw();
+        if !self.is_policy_derived() {
+            return Err(UpgradePolicySolverError::PolicyNotDerived);
+        }
+        let mask = match mask.into() {
+            EchoMask::Partial(mask) => mask.bits(),
+            EchoMask::Full(mask) if chose_continue => {
+                return Err(UpgradePolicySolverError::InvalidMask { mask: mask.bits() });
+            }
+            EchoMask::Full(_) => return Ok(0.0),
+        };
+        if calculate_num_filled_slots(mask) >= self.max_reveals {
+            return if chose_continue {
+                Err(UpgradePolicySolverError::InvalidMask { mask })
+            } else {
+                Ok(0.0)
+            };
+        }
+        if !self.caches[partial_mask_to_index(mask)].contains_score(score) {
+            return Err(UpgradePolicySolverError::InvalidScore);
+        }
+
+        // States the policy never reached are evaluated into a local memo, not the caches.
+        let mut memo: Vec<Vec<f64>> = vec![Vec::new(); NUM_PARTIAL_MASKS];
+        let advantage = self.continue_advantage_local(&mut memo, mask, score);
+
+        let regret = if chose_continue == (advantage >= 0.0) {
+            0.0
+        } else {
+            advantage.abs() / self.lambda
+        };
+        Ok(regret)
+    }
+
     pub fn weighted_expected_cost(&self) -> Result<f64, UpgradePolicySolverError> {
         if !self.is_policy_derived() {
             return Err(UpgradePolicySolverError::PolicyNotDerived);
         }
-        Ok(DP_VALUE_MULTIPLIER / self.lambda + self.cost_model.weighted_success_additional_cost())
+        Ok(DP_VALUE_MULTIPLIER / self.lambda
+            + self
+                .cost_model
+                .weighted_success_additional_cost_at(self.max_reveals))
+    }
+
+    /// Forward-difference estimate of d(weighted expected cost) / d(weight) for each buff.
+    ///
+    /// `build_scorer` turns weights into a scorer, e.g. `LinearScorer::default`, and must
+    /// rebuild this solver's score PMFs from `scorer_weights`, otherwise `ScorerMismatch`
+    /// is returned. Each perturbed copy keeps this solver's target, data blending, cost
+    /// model and settings. This runs `NUM_BUFFS + 1` full solves.
+    pub fn weight_gradient<S: InternalScorer>(
+        &self,
+        scorer_weights: &[f64; NUM_BUFFS],
+        build_scorer: impl Fn([f64; NUM_BUFFS]) -> Result<S, ScorerError>,
+        epsilon: f64,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<[f64; NUM_BUFFS], UpgradePolicySolverError> {
+        if !epsilon.is_finite() || epsilon <= 0.0 {
+            return Err(UpgradePolicySolverError::InvalidEpsilon { epsilon });
+        }
+
+        let build_solver = |weights: [f64; NUM_BUFFS]| -> Result<Self, UpgradePolicySolverError> {
+            let scorer = build_scorer(weights)
+                .map_err(|error| UpgradePolicySolverError::InvalidScorer { error })?;
+            if scorer.score_multiplier() != self.score_multiplier {
+                return Err(UpgradePolicySolverError::ScorerMismatch);
+            }
+            let mut solver = self.with_score_pmfs(scorer.build_score_pmfs(self.blend_data))?;
+            solver.scorer_weights = scorer.buff_weights();
+            Ok(solver)
+        };
+        let solve = |mut solver: Self| -> Result<f64, UpgradePolicySolverError> {
+            solver.lambda_search(tol, max_iter)?;
+            solver.weighted_expected_cost()
+        };
+
+        let base_solver = build_solver(*scorer_weights)?;
+        if base_solver.score_pmfs != self.score_pmfs {
+            return Err(UpgradePolicySolverError::ScorerMismatch);
+        }
+        let base_cost = solve(base_solver)?;
+        let mut gradient = [0.0; NUM_BUFFS];
+        for (buff_index, derivative) in gradient.iter_mut().enumerate() {
+            let mut weights = *scorer_weights;
+            weights[buff_index] += epsilon;
+            *derivative = (solve(build_solver(weights)?)? - base_cost) / epsilon;
+        }
+        Ok(gradient)
+    }
+
+    /// The single weight change of size `step` that `weight_gradient` predicts lowers the
+    /// weighted expected cost the most, as `(buff_index, signed change)`.
+    ///
+    /// A weight is only lowered if it stays non-negative. Apply the change and call again
+    /// to tune the weights greedily.
+    pub fn suggest_weight_adjustment<S: InternalScorer>(
+        &self,
+        base_weights: &[f64; NUM_BUFFS],
+        build_scorer: impl Fn([f64; NUM_BUFFS]) -> Result<S, ScorerError>,
+        step: f64,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<(usize, f64), UpgradePolicySolverError> {
+        let gradient = self.weight_gradient(base_weights, build_scorer, step, tol, max_iter)?;
+
+        let mut best: Option<(usize, f64, f64)> = None;
+        for (buff_index, &derivative) in gradient.iter().enumerate() {
+            let change = if derivative < 0.0 {
+                step
+            } else if derivative > 0.0 && base_weights[buff_index] >= step {
+                -step
+            } else {
+                continue;
+            };
+            let reduction = derivative.abs() * step;
+            if best.is_none_or(|(_, _, best_reduction)| reduction > best_reduction) {
+                best = Some((buff_index, change, reduction));
+            }
+        }
+
+        best.map(|(buff_index, change, _)| (buff_index, change))
+            .ok_or(UpgradePolicySolverError::NoImprovingWeightAdjustment)
+    }
+
+    /// How much the weighted expected cost per success rises when each buff stops counting
+    /// towards the score, relative to the derived policy's `weighted_expected_cost`.
+    ///
+    /// Buff `i` is dropped by giving it a score of 0 for every value, which is the same as
+    /// zeroing its weight, and the policy is solved again with everything else unchanged.
+    /// A large entry means the target leans on that buff; an entry near 0 means chasing it
+    /// doesn't matter, and `f64::INFINITY` means the target is unreachable without it.
+    /// This runs `NUM_BUFFS` full solves.
+    pub fn cost_attribution(
+        &self,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<[f64; NUM_BUFFS], UpgradePolicySolverError> {
+        let base_cost = self.weighted_expected_cost()?;
+
+        let mut attribution = [0.0; NUM_BUFFS];
+        for (buff_index, contribution) in attribution.iter_mut().enumerate() {
+            let mut score_pmfs = self.score_pmfs.clone();
+            score_pmfs[buff_index] = vec![(0, 1.0)];
+            let mut solver = match self.with_score_pmfs(score_pmfs) {
+                Ok(solver) => solver,
+                Err(UpgradePolicySolverError::TargetScoreImpossible { .. }) => {
+                    *contribution = f64::INFINITY;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            solver.lambda_search(tol, max_iter)?;
+            *contribution = solver.weighted_expected_cost()? - base_cost;
+        }
+        Ok(attribution)
+    }
+
+    /// A solver over `score_pmfs` with this solver's target, cost model and settings.
+    fn with_score_pmfs(
+        &self,
+        score_pmfs: Vec<Vec<(u16, f64)>>,
+    ) -> Result<Self, UpgradePolicySolverError> {
+        let mut solver = Self::from_pmfs_impl(
+            score_pmfs,
+            self.blend_data,
+            self.target_score as f64 / self.score_multiplier,
+            self.score_multiplier,
+            self.cost_model,
+            DEFAULT_PMF_SUM_TOL,
+            Instant::now(),
+        )?;
+        let mut buff_max_scores = solver.buff_max_score;
+        buff_max_scores.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+        let max_possible_score = buff_max_scores[..self.max_reveals].iter().sum();
+        validate_target_score(solver.target_score, max_possible_score)?;
+
+        solver.max_possible_score = max_possible_score;
+        solver.max_reveals = self.max_reveals;
+        solver.reward_slope = self.reward_slope;
+        solver.reforge_cost = self.reforge_cost;
+        solver.scorer_weights = self.scorer_weights;
+        solver.max_lambda_expansions = self.max_lambda_expansions;
+        Ok(solver)
+    }
+
+    /// `(ratio, weighted_expected_cost)` for each exp refund ratio in `ratios`.
+    ///
+    /// The refund ratio changes the reveal cost, so every ratio is a full `lambda_search`.
+    /// Afterwards the cost model is restored and the policy (if any) is derived again at
+    /// the previous lambda, so expected resources must be recalculated.
+    pub fn cost_across_refund(
+        &mut self,
+        ratios: &[f64],
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<Vec<(f64, f64)>, UpgradePolicySolverError> {
+        let original_cost_model = self.cost_model;
+        let previous_lambda = self.is_policy_derived.then_some(self.lambda);
+
+        let mut solve = || {
+            let mut costs = Vec::with_capacity(ratios.len());
+            for &ratio in ratios.iter() {
+                self.cost_model = original_cost_model
+                    .clone_with_refund(ratio)
+                    .map_err(|error| UpgradePolicySolverError::InvalidCostModel { error })?;
+                self.lambda_search(tol, max_iter)?;
+                costs.push((ratio, self.weighted_expected_cost()?));
+            }
+            Ok(costs)
+        };
+        let result = solve();
+
+        self.cost_model = original_cost_model;
+        match previous_lambda {
+            Some(lambda) => self.derive_policy_at_lambda(lambda),
+            None => self.clear_caches(),
+        }
+        result
+    }
+
+    /// The finite-difference derivative of the weighted expected cost per success with
+    /// respect to the display target, `(cost(target + delta) - cost(target)) / delta`.
+    ///
+    /// `delta` is applied after rounding to internal score units and may be negative.
+    /// Restores the target and the policy like `cost_across_refund`.
+    pub fn marginal_cost_of_target(
+        &mut self,
+        delta: f64,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        if !delta.is_finite() {
+            return Err(UpgradePolicySolverError::InvalidTargetDelta { delta });
+        }
+        let original_target_score = self.target_score;
+        let target_score_display = original_target_score as f64 / self.score_multiplier;
+        let shifted_target_score =
+            normalize_target_score(target_score_display + delta, self.score_multiplier)?;
+        if shifted_target_score == original_target_score {
+            return Err(UpgradePolicySolverError::InvalidTargetDelta { delta });
+        }
+        validate_target_score(shifted_target_score, self.max_possible_score)?;
+        let previous_lambda = self.is_policy_derived.then_some(self.lambda);
+
+        let mut solve = || {
+            self.lambda_search(tol, max_iter)?;
+            let base_cost = self.weighted_expected_cost()?;
+            self.clear_caches();
+            self.target_score = shifted_target_score;
+            self.lambda_search(tol, max_iter)?;
+            let shifted_cost = self.weighted_expected_cost()?;
+            let applied_delta = (shifted_target_score as f64 - original_target_score as f64)
+                / self.score_multiplier;
+            Ok((shifted_cost - base_cost) / applied_delta)
+        };
+        let result = solve();
+
+        self.clear_caches();
+        self.target_score = original_target_score;
+        if let Some(lambda) = previous_lambda {
+            self.derive_policy_at_lambda(lambda);
+        }
+        result
+    }
+
+    /// The weighted expected cost per success of the naive policy that never abandons,
+    /// i.e. every echo is upgraded until all slots are revealed.
+    ///
+    /// This does not depend on the derived policy. Like `weighted_expected_cost`, it ignores
+    /// the cost model's abandon refund: failed echoes are paid in full.
+    pub fn naive_expected_cost(&self) -> Result<f64, UpgradePolicySolverError> {
+        let mut memo: Vec<Vec<f64>> = vec![Vec::new(); NUM_PARTIAL_MASKS];
+        let success_probability = self.full_reveal_success_rec(&mut memo, 0u16, 0u16);
+        Ok(self.full_reveal_weighted_cost() / success_probability
+            + self
+                .cost_model
+                .weighted_success_additional_cost_at(self.max_reveals))
+    }
+
+    /// The weighted expected cost per success when an echo with `k` filled slots is continued
+    /// iff its internal score is at least `thresholds[k]`, whatever its buffs.
+    ///
+    /// `thresholds[0]` applies to the empty echo, so it should be 0. Every reveal the rule
+    /// pays for is counted, even when the target is already out of reach, and the abandon
+    /// refund is not paid back, matching `weighted_expected_cost`. Compare with it to see
+    /// what the mask-dependent policy saves; this does not depend on the derived policy.
+    pub fn fixed_threshold_cost(
+        &self,
+        thresholds: [u16; NUM_ECHO_SLOTS],
+    ) -> Result<f64, UpgradePolicySolverError> {
+        let mut memo: Vec<Vec<(f64, f64)>> = vec![Vec::new(); NUM_PARTIAL_MASKS];
+        let (success_probability, attempt_cost) =
+            self.fixed_threshold_rec(&thresholds, &mut memo, 0u16, 0u16);
+        if success_probability <= 0.0 {
+            return Err(UpgradePolicySolverError::ThresholdsNeverSucceed);
+        }
+        Ok(
+            (attempt_cost + self.cost_model.echo_acquisition_cost()) / success_probability
+                + self
+                    .cost_model
+                    .weighted_success_additional_cost_at(self.max_reveals),
+        )
+    }
+
+    /// The thresholds minimizing `fixed_threshold_cost`, found by local search from the
+    /// derived policy's `threshold_curve`.
+    ///
+    /// Each stage from 1 to 4 is moved by a step while that lowers the cost, halving the
+    /// step down to 1, until no single move helps. The result is a local optimum and
+    /// `thresholds[0]` is always 0.
+    pub fn best_fixed_thresholds(&self) -> Result<[u16; NUM_ECHO_SLOTS], UpgradePolicySolverError> {
+        let curve = self.threshold_curve()?;
+        let mut thresholds: [u16; NUM_ECHO_SLOTS] = std::array::from_fn(|num_filled_slots| {
+            curve[num_filled_slots].unwrap_or(self.target_score)
+        });
+        thresholds[0] = 0;
+        let mut best_cost = self.fixed_threshold_cost(thresholds)?;
+
+        let mut step = (self.target_score / 4).max(1);
+        loop {
+            let mut improved = false;
+            for num_filled_slots in 1..NUM_ECHO_SLOTS {
+                for candidate in [
+                    thresholds[num_filled_slots].saturating_sub(step),
+                    thresholds[num_filled_slots].saturating_add(step),
+                ] {
+                    let mut candidate_thresholds = thresholds;
+                    candidate_thresholds[num_filled_slots] = candidate;
+                    if let Ok(cost) = self.fixed_threshold_cost(candidate_thresholds)
+                        && cost < best_cost
+                    {
+                        best_cost = cost;
+                        thresholds = candidate_thresholds;
+                        improved = true;
+                    }
+                }
+            }
+            if !improved {
+                if step == 1 {
+                    break;
+                }
+                step /= 2;
+            }
+        }
+        Ok(thresholds)
+    }
+
+    /// `(success probability, weighted reveal cost)` from `(mask, score)` under
+    /// `fixed_threshold_cost`'s rule.
+    fn fixed_threshold_rec(
+        &self,
+        thresholds: &[u16; NUM_ECHO_SLOTS],
+        memo: &mut [Vec<(f64, f64)>],
+        mask: u16,
+        score: u16,
+    ) -> (f64, f64) {
+        let num_filled_slots = calculate_num_filled_slots(mask);
+        if num_filled_slots >= self.max_reveals {
+            return (f64::from(u8::from(score >= self.target_score)), 0.0);
+        }
+        if score < thresholds[num_filled_slots] {
+            return (0.0, 0.0);
+        }
+
+        let cache_index = partial_mask_to_index(mask);
+        let score_key = self.caches[cache_index].score_to_index(score);
+        if memo[cache_index].is_empty() {
+            memo[cache_index] = vec![(f64::NAN, f64::NAN); self.caches[cache_index].dp.len()];
+        }
+        let entry = memo[cache_index][score_key];
+        if !entry.0.is_nan() {
+            return entry;
+        }
+
+        let num_remaining_buffs = NUM_BUFFS - num_filled_slots;
+        let mut success_total: f64 = 0.0;
+        let mut cost_total: f64 = 0.0;
+        let mut multiplier_total: f64 = 0.0;
+        let mut remaining_buffs = MASK_ALL ^ mask;
+        while remaining_buffs != 0 {
+            let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
+            let index = lsb.trailing_zeros() as usize;
+            remaining_buffs ^= lsb;
+            let next_mask = mask | (1u16 << index);
+            multiplier_total += self.cost_model.buff_reveal_multiplier(index);
+
+            for j in 0..self.pmf_len[index] {
+                let (delta, probability) = self.score_pmf_entry(index, j);
+                let (next_success, next_cost) =
+                    self.fixed_threshold_rec(thresholds, memo, next_mask, score + delta);
+                success_total += probability * next_success;
+                cost_total += probability * next_cost;
+            }
+        }
+
+        let reveal_cost = self.cost_model.weighted_reveal_cost(num_filled_slots) * multiplier_total;
+        let entry = (
+            success_total / num_remaining_buffs as f64,
+            (reveal_cost + cost_total) / num_remaining_buffs as f64,
+        );
+        memo[cache_index][score_key] = entry;
+        entry
+    }
+
+    /// The weighted cost of one echo, acquisition included, upgraded until `max_reveals`
+    /// slots are revealed. No abandon refund is subtracted.
+    fn full_reveal_weighted_cost(&self) -> f64 {
+        // Every slot reveals each buff with equal probability when nothing is abandoned.
+        let mean_multiplier: f64 = (0..NUM_BUFFS)
+            .map(|buff_index| self.cost_model.buff_reveal_multiplier(buff_index))
+            .sum::<f64>()
+            / NUM_BUFFS as f64;
+        let cost_per_echo: f64 = (0..self.max_reveals)
+            .map(|slot| self.cost_model.weighted_reveal_cost(slot))
+            .sum();
+        cost_per_echo * mean_multiplier + self.cost_model.echo_acquisition_cost()
+    }
+
+    /// Whether every fully revealed echo reaches the target, see `guaranteed_target`.
+    pub fn is_trivially_achievable(&self) -> bool {
+        self.target_score <= self.guaranteed_target()
     }
 }
 
 impl UpgradePolicySolver {
-    pub fn new<S: InternalScorer>(
+    pub fn new<S: InternalScorer + ?Sized>(
         scorer: &S,
         blend_data: bool,
         target_score_display: f64,
         cost_model: CostModel,
     ) -> Result<Self, UpgradePolicySolverError> {
-        let target_score = normalize_target_score(target_score_display)?;
+        let pmf_start = Instant::now();
+        let score_pmfs = scorer.build_score_pmfs(blend_data);
+        let mut solver = Self::from_pmfs_impl(
+            score_pmfs,
+            blend_data,
+            target_score_display,
+            scorer.score_multiplier(),
+            cost_model,
+            DEFAULT_PMF_SUM_TOL,
+            pmf_start,
+        )?;
+        solver.scorer_weights = scorer.buff_weights();
+        Ok(solver)
+    }
+
+    /// Create a solver from per-buff score PMFs instead of a scorer.
+    ///
+    /// `score_pmfs[i]` lists `(internal score, probability)` for buff `i`. Each PMF must sum
+    /// to 1 within `pmf_sum_tol`; pass `DEFAULT_PMF_SUM_TOL` unless the probabilities carry
+    /// accumulated floating-point rounding. Solvers built this way report `blend_data` as
+    /// false and use `SCORE_MULTIPLIER`.
+    pub fn from_pmfs(
+        score_pmfs: Vec<Vec<(u16, f64)>>,
+        target_score_display: f64,
+        cost_model: CostModel,
+        pmf_sum_tol: f64,
+    ) -> Result<Self, UpgradePolicySolverError> {
+        if !pmf_sum_tol.is_finite() || pmf_sum_tol < 0.0 {
+            return Err(UpgradePolicySolverError::InvalidTolerance {
+                tolerance: pmf_sum_tol,
+            });
+        }
+        Self::from_pmfs_impl(
+            score_pmfs,
+            false,
+            target_score_display,
+            SCORE_MULTIPLIER,
+            cost_model,
+            pmf_sum_tol,
+            Instant::now(),
+        )
+    }
+
+    fn from_pmfs_impl(
+        score_pmfs: Vec<Vec<(u16, f64)>>,
+        blend_data: bool,
+        target_score_display: f64,
+        score_multiplier: f64,
+        cost_model: CostModel,
+        pmf_sum_tol: f64,
+        pmf_start: Instant,
+    ) -> Result<Self, UpgradePolicySolverError> {
+        let target_score = normalize_target_score(target_score_display, score_multiplier)?;
         let ScorePmfAnalysis {
             score_pmfs,
             buff_min_score,
             buff_max_score,
             pmf_len,
             max_possible_score,
-        } = analyze_score_pmfs(scorer, blend_data)?;
+        } = analyze_score_pmfs(score_pmfs, pmf_sum_tol)?;
+        let pmf_construction = pmf_start.elapsed();
+        log::debug!(
+            "score pmfs built: blend_data={blend_data} pmf_len={pmf_len:?} \
+             max_possible_score={max_possible_score} target_score={target_score} \
+             elapsed={pmf_construction:?}"
+        );
         validate_target_score(target_score, max_possible_score)?;
 
         let mut caches: Vec<MaskCache> = Vec::with_capacity(NUM_PARTIAL_MASKS);
@@ -461,28 +2039,402 @@ impl UpgradePolicySolver {
             cost_model,
             lambda: 0.0,
             is_policy_derived: false,
+            reward_slope: 0.0,
+            reforge_cost: None,
+            blend_data,
+            scorer_weights: None,
+            max_reveals: NUM_ECHO_SLOTS,
+            max_lambda_expansions: DEFAULT_MAX_LAMBDA_EXPANSIONS,
 
             pmf_len,
+            buff_min_score,
+            buff_max_score,
             max_possible_score,
+            score_multiplier,
             caches,
             touched_cache: Vec::new(),
             expected_cost_cache: ExpectedCostCache::NotComputed,
+
+            timing_enabled: false,
+            timings: Timings {
+                pmf_construction,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// The highest display target whose optimal policy succeeds with probability at least
+    /// `min_success`.
+    ///
+    /// Binary searches the internal target score, deriving the policy at each step.
+    pub fn max_target_for_success<S: InternalScorer>(
+        scorer: &S,
+        blend_data: bool,
+        cost_model: CostModel,
+        min_success: f64,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        if !(min_success > 0.0 && min_success < 1.0) {
+            return Err(UpgradePolicySolverError::InvalidSuccessProbability {
+                probability: min_success,
+            });
+        }
+
+        let mut solver = Self::new(scorer, blend_data, 0.0, cost_model)?;
+        let mut lo: u16 = 0;
+        let mut hi: u16 = solver.max_possible_score;
+        while lo < hi {
+            let mid = lo + (hi - lo).div_ceil(2);
+            solver.update_target_score(RawScore::new(mid))?;
+            solver.lambda_search(tol, max_iter)?;
+            let success_probability = solver.calculate_expected_resources()?.success_probability();
+            if success_probability >= min_success {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        Ok(lo as f64 / solver.score_multiplier)
+    }
+
+    /// The highest display target whose weighted expected cost per success is within `budget`.
+    ///
+    /// Binary searches the internal target score like `max_target_for_success`.
+    pub fn target_for_budget<S: InternalScorer>(
+        scorer: &S,
+        blend_data: bool,
+        cost_model: CostModel,
+        budget: f64,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        if !budget.is_finite() || budget <= 0.0 {
+            return Err(UpgradePolicySolverError::InvalidBudget { budget });
+        }
+
+        let mut solver = Self::new(scorer, blend_data, 0.0, cost_model)?;
+        solver.lambda_search(tol, max_iter)?;
+        let min_cost = solver.weighted_expected_cost()?;
+        if min_cost > budget {
+            return Err(UpgradePolicySolverError::BudgetTooSmall { budget, min_cost });
+        }
+
+        let mut lo: u16 = 0;
+        let mut hi: u16 = solver.max_possible_score;
+        while lo < hi {
+            let mid = lo + (hi - lo).div_ceil(2);
+            solver.update_target_score(RawScore::new(mid))?;
+            solver.lambda_search(tol, max_iter)?;
+            if solver.weighted_expected_cost()? <= budget {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        Ok(lo as f64 / solver.score_multiplier)
+    }
+
+    /// The weighted expected cost per success when an echo succeeds by reaching any one of
+    /// `scorers_targets` (each a scorer and its display target).
+    ///
+    /// Every slot is scored by all scorers at once, so the policy tracks one score per target
+    /// and is derived separately from any `UpgradePolicySolver`. Supports 1 to
+    /// `MAX_FIRST_OF_TARGETS` targets with the full `NUM_ECHO_SLOTS` reveals.
+    pub fn first_of(
+        scorers_targets: &[(&dyn InternalScorer, f64)],
+        blend_data: bool,
+        cost_model: CostModel,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        let mut solver = FirstOfSolver::new(scorers_targets, blend_data, cost_model)?;
+        let lambda = solver.lambda_search(tol, max_iter)?;
+        Ok(DP_VALUE_MULTIPLIER / lambda
+            + cost_model.weighted_success_additional_cost_at(NUM_ECHO_SLOTS))
+    }
+
+    /// Solve `target_score_display` under `LinearScorer::default(linear_weights)` and
+    /// `FixedScorer::new(fixed_weights)` and report both costs and success probabilities.
+    ///
+    /// A fixed echo's display score is its weight sum / `SCORE_MULTIPLIER`, so pick fixed
+    /// weights on a comparable scale.
+    pub fn compare_scorers(
+        linear_weights: [f64; NUM_BUFFS],
+        fixed_weights: [u16; NUM_BUFFS],
+        blend_data: bool,
+        target_score_display: f64,
+        cost_model: CostModel,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<ScorerComparison, UpgradePolicySolverError> {
+        let solve = |scorer: &dyn InternalScorer| -> Result<(f64, f64), UpgradePolicySolverError> {
+            let mut solver = Self::new(scorer, blend_data, target_score_display, cost_model)?;
+            solver.lambda_search(tol, max_iter)?;
+            let success_probability = solver.calculate_expected_resources()?.success_probability();
+            Ok((solver.weighted_expected_cost()?, success_probability))
+        };
+
+        let linear_scorer = LinearScorer::default(linear_weights)
+            .map_err(|error| UpgradePolicySolverError::InvalidScorer { error })?;
+        let fixed_scorer = FixedScorer::new(fixed_weights)
+            .map_err(|error| UpgradePolicySolverError::InvalidScorer { error })?;
+        let (linear_expected_cost, linear_success_probability) = solve(&linear_scorer)?;
+        let (fixed_expected_cost, fixed_success_probability) = solve(&fixed_scorer)?;
+        Ok(ScorerComparison {
+            linear_expected_cost,
+            linear_success_probability,
+            fixed_expected_cost,
+            fixed_success_probability,
         })
     }
 
-    pub fn update_target_score(
-        &mut self,
-        new_target_score_display: f64,
-    ) -> Result<(), UpgradePolicySolverError> {
-        let new_target_score = normalize_target_score(new_target_score_display)?;
-        validate_target_score(new_target_score, self.max_possible_score)?;
-        self.clear_caches();
-        self.target_score = new_target_score;
-        Ok(())
+    /// Create a solver whose reward for reaching the target grows with the final score.
+    ///
+    /// A kept echo with final display score `score >= target` is worth
+    /// `1.0 + slope * (score - target)` successes, so `weighted_expected_cost` becomes the
+    /// cost per unit of reward. `slope = 0.0` is the same as `new`.
+    pub fn new_with_linear_reward<S: InternalScorer>(
+        scorer: &S,
+        blend_data: bool,
+        target_score_display: f64,
+        cost_model: CostModel,
+        slope: f64,
+    ) -> Result<Self, UpgradePolicySolverError> {
+        if !slope.is_finite() || slope < 0.0 {
+            return Err(UpgradePolicySolverError::InvalidRewardSlope { slope });
+        }
+        let mut solver = Self::new(scorer, blend_data, target_score_display, cost_model)?;
+        solver.reward_slope = slope;
+        Ok(solver)
+    }
+
+    /// Create a solver where a fully revealed echo that misses the target may be reforged
+    /// instead of abandoned.
+    ///
+    /// A reforge costs `reforge_cost` (weighted, no tuner or exp) and rerolls the last
+    /// revealed slot: a new buff is drawn uniformly from those the other slots don't have,
+    /// with a new value, exactly like the last reveal. It may be repeated, and the policy
+    /// either reforges until the target is reached or abandons right away. The solver only
+    /// tracks the score sum, so the last slot stands in for the worst one, whose score is
+    /// unknown. `naive_expected_cost` and `fixed_threshold_cost` ignore reforging.
+    pub fn with_reforge<S: InternalScorer>(
+        scorer: &S,
+        blend_data: bool,
+        target_score_display: f64,
+        cost_model: CostModel,
+        reforge_cost: f64,
+    ) -> Result<Self, UpgradePolicySolverError> {
+        if !reforge_cost.is_finite() || reforge_cost < 0.0 {
+            return Err(UpgradePolicySolverError::InvalidReforgeCost { cost: reforge_cost });
+        }
+        let mut solver = Self::new(scorer, blend_data, target_score_display, cost_model)?;
+        solver.reforge_cost = Some(reforge_cost);
+        Ok(solver)
+    }
+
+    pub fn reforge_cost(&self) -> Option<f64> {
+        self.reforge_cost
+    }
+
+    /// Create a solver that reveals at most `max_reveals` slots per echo.
+    ///
+    /// An echo with `max_reveals` revealed slots is final and kept iff it reaches the target.
+    pub fn new_with_max_reveals<S: InternalScorer>(
+        scorer: &S,
+        blend_data: bool,
+        target_score_display: f64,
+        cost_model: CostModel,
+        max_reveals: usize,
+    ) -> Result<Self, UpgradePolicySolverError> {
+        if !(1..=NUM_ECHO_SLOTS).contains(&max_reveals) {
+            return Err(UpgradePolicySolverError::InvalidMaxReveals { max_reveals });
+        }
+        let mut solver = Self::new(scorer, blend_data, target_score_display, cost_model)?;
+
+        let mut buff_max_scores = solver.buff_max_score;
+        buff_max_scores.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+        let max_possible_score = buff_max_scores[..max_reveals].iter().sum();
+        validate_target_score(solver.target_score, max_possible_score)?;
+
+        solver.max_possible_score = max_possible_score;
+        solver.max_reveals = max_reveals;
+        Ok(solver)
+    }
+
+    /// Record the time spent in `lambda_search` and `calculate_expected_resources`.
+    pub fn with_timing(mut self) -> Self {
+        self.timing_enabled = true;
+        self
+    }
+
+    pub fn update_target_score(
+        &mut self,
+        new_target_score: RawScore,
+    ) -> Result<(), UpgradePolicySolverError> {
+        let new_target_score = new_target_score.into_raw();
+        validate_target_score(new_target_score, self.max_possible_score)?;
+        self.clear_caches();
+        self.target_score = new_target_score;
+        Ok(())
+    }
+
+    /// `update_target_score` with a display target; targets <= 0 become 0.
+    pub fn update_target_score_display(
+        &mut self,
+        new_target_score_display: f64,
+    ) -> Result<(), UpgradePolicySolverError> {
+        let new_target_score =
+            normalize_target_score(new_target_score_display, self.score_multiplier)?;
+        self.update_target_score(RawScore::new(new_target_score))
+    }
+
+    /// Solve the same problem with and without blended histograms.
+    ///
+    /// Returns `(blended, unblended)` weighted expected costs per success.
+    pub fn blend_impact<S: InternalScorer>(
+        scorer: &S,
+        target_score_display: f64,
+        cost_model: CostModel,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<(f64, f64), UpgradePolicySolverError> {
+        let mut blended = Self::new(scorer, true, target_score_display, cost_model)?;
+        blended.lambda_search(tol, max_iter)?;
+        let mut unblended = Self::new(scorer, false, target_score_display, cost_model)?;
+        unblended.lambda_search(tol, max_iter)?;
+        Ok((
+            blended.weighted_expected_cost()?,
+            unblended.weighted_expected_cost()?,
+        ))
+    }
+
+    /// `get_success_probability` at `(mask, score)` with blended histograms minus the same
+    /// without, each under its own optimal policy. Positive means blending is more optimistic.
+    pub fn state_blend_delta<S: InternalScorer>(
+        scorer: &S,
+        target_score_display: f64,
+        cost_model: CostModel,
+        mask: impl Into<EchoMask>,
+        score: RawScore,
+        tol: f64,
+        max_iter: usize,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        let mask = mask.into();
+        let mut probabilities = [0.0; 2];
+        for (probability, blend_data) in probabilities.iter_mut().zip([true, false]) {
+            let mut solver = Self::new(scorer, blend_data, target_score_display, cost_model)?;
+            solver.lambda_search(tol, max_iter)?;
+            solver.calculate_expected_resources()?;
+            *probability = solver.get_success_probability(mask, score)?;
+        }
+        Ok(probabilities[0] - probabilities[1])
+    }
+
+    /// The inputs behind this solver's results, see `ConfigFingerprint`.
+    pub fn config_fingerprint(&self) -> ConfigFingerprint {
+        let cost_weights = self.cost_model.weights();
+        let buff_reveal_multipliers: [f64; NUM_BUFFS] =
+            std::array::from_fn(|buff_index| self.cost_model.buff_reveal_multiplier(buff_index));
+
+        let mut hasher = Fnv1a::new();
+        for weight in self.scorer_weights.iter().flatten() {
+            hasher.write_f64(*weight);
+        }
+        hasher.write(&[self.scorer_weights.is_some() as u8, self.blend_data as u8]);
+        hasher.write(&self.target_score.to_le_bytes());
+        hasher.write_f64(self.score_multiplier);
+        hasher.write(&(self.max_reveals as u64).to_le_bytes());
+        hasher.write_f64(self.reward_slope);
+        hasher.write_f64(self.reforge_cost.unwrap_or(f64::NAN));
+        hasher.write_f64(cost_weights.0);
+        hasher.write_f64(cost_weights.1);
+        hasher.write_f64(cost_weights.2);
+        hasher.write_f64(self.cost_model.exp_refund_ratio());
+        hasher.write_f64(self.cost_model.abandon_refund_ratio());
+        hasher.write_f64(self.cost_model.echo_acquisition_cost());
+        for &multiplier in buff_reveal_multipliers.iter() {
+            hasher.write_f64(multiplier);
+        }
+        for pmf in self.score_pmfs.iter() {
+            hasher.write(&(pmf.len() as u64).to_le_bytes());
+            for &(score, probability) in pmf.iter() {
+                hasher.write(&score.to_le_bytes());
+                hasher.write_f64(probability);
+            }
+        }
+
+        ConfigFingerprint {
+            scorer_weights: self.scorer_weights,
+            blend_data: self.blend_data,
+            target_score: self.target_score,
+            score_multiplier: self.score_multiplier,
+            max_reveals: self.max_reveals,
+            reward_slope: self.reward_slope,
+            reforge_cost: self.reforge_cost,
+            cost_weights,
+            exp_refund_ratio: self.cost_model.exp_refund_ratio(),
+            abandon_refund_ratio: self.cost_model.abandon_refund_ratio(),
+            echo_acquisition_cost: self.cost_model.echo_acquisition_cost(),
+            buff_reveal_multipliers,
+            hash: format!("{:016x}", hasher.0),
+        }
     }
 }
 
 impl UpgradePolicySolver {
+    /// Read the `j`-th entry of the score PMF for `buff_index`.
+    ///
+    /// Indices are only checked in debug builds.
+    #[inline(always)]
+    fn score_pmf_entry(&self, buff_index: usize, j: usize) -> (u16, f64) {
+        debug_assert!(
+            buff_index < NUM_BUFFS && j < self.pmf_len[buff_index],
+            "score_pmf_entry: invalid entry {j} for buff index {buff_index}"
+        );
+        self.score_pmfs[buff_index][j]
+    }
+
+    /// Add the score PMF of every buff set extending `scores` (with `num_revealed` buffs below
+    /// `first_buff`) to `total`, counting the sets in `num_buff_sets`.
+    fn accumulate_raw_scores(
+        &self,
+        first_buff: usize,
+        num_revealed: usize,
+        scores: &[f64],
+        total: &mut [f64],
+        num_buff_sets: &mut usize,
+    ) {
+        if num_revealed == self.max_reveals {
+            for (sum, &probability) in total.iter_mut().zip(scores.iter()) {
+                *sum += probability;
+            }
+            *num_buff_sets += 1;
+            return;
+        }
+
+        let last_buff = NUM_BUFFS - (self.max_reveals - num_revealed);
+        for buff_index in first_buff..=last_buff {
+            let mut next_scores = vec![0.0; scores.len()];
+            for (score, &probability) in scores.iter().enumerate() {
+                if probability == 0.0 {
+                    continue;
+                }
+                for j in 0..self.pmf_len[buff_index] {
+                    let (delta, delta_probability) = self.score_pmf_entry(buff_index, j);
+                    next_scores[score + delta as usize] += probability * delta_probability;
+                }
+            }
+            self.accumulate_raw_scores(
+                buff_index + 1,
+                num_revealed + 1,
+                &next_scores,
+                total,
+                num_buff_sets,
+            );
+        }
+    }
+
     fn clear_caches(&mut self) {
         self.lambda = 0.0;
         self.is_policy_derived = false;
@@ -512,35 +2464,66 @@ impl UpgradePolicySolver {
         &mut self,
         tol: f64,
         max_iter: usize,
+    ) -> Result<f64, UpgradePolicySolverError> {
+        let start = Instant::now();
+        let result = self.lambda_search_impl(tol, max_iter);
+        if self.timing_enabled {
+            self.timings.lambda_search = Some(start.elapsed());
+        }
+        result
+    }
+
+    fn lambda_search_impl(
+        &mut self,
+        tol: f64,
+        max_iter: usize,
     ) -> Result<f64, UpgradePolicySolverError> {
         if tol.is_nan() || tol.is_infinite() || tol <= 0.0 {
             return Err(UpgradePolicySolverError::InvalidTolerance { tolerance: tol });
         }
 
+        // Always continuing succeeds with certainty, so the root is where the flat reward
+        // equals the full reveal cost. Fall back to the search if the policy still abandons.
+        if self.reward_slope == 0.0 && self.is_trivially_achievable() {
+            let lambda = DP_VALUE_MULTIPLIER / self.full_reveal_weighted_cost();
+            let advantage = self.root_advantage(lambda);
+            if advantage.abs() <= tol {
+                log::debug!("lambda search: target is trivially achievable, lambda={lambda}");
+                return Ok(lambda);
+            }
+        }
+
         let lo = 0.0;
         let mut hi = 1.0;
 
         let mut fa = self.root_advantage(lo);
         if fa < 0.0 {
+            log::debug!("lambda search: root_advantage({lo})={fa} is negative");
             return Err(UpgradePolicySolverError::LambdaNotBracketed);
         }
         let mut fb = self.root_advantage(hi);
         let mut expand_count: usize = 0;
-        while fb > 0.0 && expand_count < 80 {
+        while fb > 0.0 && expand_count < self.max_lambda_expansions {
             hi *= 2.0;
             fb = self.root_advantage(hi);
             expand_count += 1;
         }
         if fb > 0.0 {
-            return Err(UpgradePolicySolverError::LambdaNotBracketed);
+            log::debug!("lambda search: not bracketed after {expand_count} expansions, hi={hi}");
+            return Err(UpgradePolicySolverError::LambdaBracketExpansionExhausted {
+                attempts: expand_count,
+            });
         }
+        log::debug!("lambda search: bracketed [{lo}, {hi}] after {expand_count} expansions");
 
         let mut a = lo;
         let mut b = hi;
         let mut scale_a = 1.0f64;
         let mut scale_b = 1.0f64;
+        let mut last_candidate = hi;
+        let mut last_advantage = fb;
 
-        for _ in 0..max_iter {
+        for iter in 0..max_iter {
             let fa_s = fa * scale_a;
             let fb_s = fb * scale_b;
             let denom = fb_s - fa_s;
@@ -552,7 +2535,14 @@ impl UpgradePolicySolver {
             };
 
             let fc = self.root_advantage(c);
+            log::trace!("lambda search: iter={iter} lambda={c} root_advantage={fc}");
+            last_candidate = c;
+            last_advantage = fc;
             if fc.abs() <= tol {
+                log::debug!(
+                    "lambda search: converged after {} iterations, lambda={c}",
+                    iter + 1
+                );
                 return Ok(c);
             }
 
@@ -571,10 +2561,19 @@ impl UpgradePolicySolver {
             if (b - a).abs() <= tol * (1.0 + c.abs()) {
                 let c = 0.5 * (a + b);
                 self.root_advantage(c);
+                log::debug!(
+                    "lambda search: bracket collapsed after {} iterations, lambda={c}",
+                    iter + 1
+                );
                 return Ok(c);
             }
         }
-        Err(UpgradePolicySolverError::LambdaNotFoundWithinMaxIter)
+        log::debug!("lambda search: no root within {max_iter} iterations, bracket=[{a}, {b}]");
+        Err(UpgradePolicySolverError::LambdaNotFoundWithinMaxIter {
+            last_candidate,
+            last_advantage,
+            bracket: (a, b),
+        })
     }
 
     fn root_advantage(&mut self, lambda: f64) -> f64 {
@@ -583,28 +2582,152 @@ impl UpgradePolicySolver {
         self.is_policy_derived = true;
 
         let mut total: f64 = 0.0;
+        let mut multiplier_total: f64 = 0.0;
         let mut remaining_buffs = MASK_ALL;
         while remaining_buffs != 0 {
             let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
             let index = lsb.trailing_zeros() as usize;
             remaining_buffs ^= lsb;
             let next_mask = 1u16 << index;
+            multiplier_total += self.cost_model.buff_reveal_multiplier(index);
 
             for j in 0..self.pmf_len[index] {
-                let (delta, probability) = self.score_pmfs[index][j];
+                let (delta, probability) = self.score_pmf_entry(index, j);
                 total += probability * self.value_rec(next_mask, delta);
             }
         }
 
         let expected = total / NUM_BUFFS as f64;
-        expected - lambda * self.cost_model.weighted_reveal_cost(0)
+        let reveal_cost =
+            self.cost_model.weighted_reveal_cost(0) * multiplier_total / NUM_BUFFS as f64;
+        expected - lambda * (reveal_cost + self.cost_model.echo_acquisition_cost())
+    }
+
+    /// The value of revealing one more slot at `(mask, score)` minus its cost, before the
+    /// `max(0, _)` of `value_rec`.
+    fn continue_advantage(&mut self, mask: u16, score: u16) -> f64 {
+        let num_filled_slots = calculate_num_filled_slots(mask);
+        let mut total: f64 = 0.0;
+        let mut success_total: f64 = 0.0;
+        let mut multiplier_total: f64 = 0.0;
+        let mut remaining_buffs = MASK_ALL ^ mask;
+        while remaining_buffs != 0 {
+            let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
+            let idx = lsb.trailing_zeros() as usize;
+            remaining_buffs ^= lsb;
+            let next_mask = mask | (1u16 << idx);
+            multiplier_total += self.cost_model.buff_reveal_multiplier(idx);
+
+            for j in 0..self.pmf_len[idx] {
+                let (delta, probability) = self.score_pmf_entry(idx, j);
+                total += probability * self.value_rec(next_mask, score + delta);
+                if score + delta >= self.target_score {
+                    success_total += probability;
+                }
+            }
+        }
+
+        self.advantage_from_totals(num_filled_slots, total, success_total, multiplier_total)
+    }
+
+    /// `continue_advantage` without writing to the caches, see `value_local`.
+    fn continue_advantage_local(&self, memo: &mut [Vec<f64>], mask: u16, score: u16) -> f64 {
+        let num_filled_slots = calculate_num_filled_slots(mask);
+        let mut total: f64 = 0.0;
+        let mut success_total: f64 = 0.0;
+        let mut multiplier_total: f64 = 0.0;
+        let mut remaining_buffs = MASK_ALL ^ mask;
+        while remaining_buffs != 0 {
+            let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
+            let idx = lsb.trailing_zeros() as usize;
+            remaining_buffs ^= lsb;
+            let next_mask = mask | (1u16 << idx);
+            multiplier_total += self.cost_model.buff_reveal_multiplier(idx);
+
+            for j in 0..self.pmf_len[idx] {
+                let (delta, probability) = self.score_pmf_entry(idx, j);
+                total += probability * self.value_local(memo, next_mask, score + delta);
+                if score + delta >= self.target_score {
+                    success_total += probability;
+                }
+            }
+        }
+
+        self.advantage_from_totals(num_filled_slots, total, success_total, multiplier_total)
+    }
+
+    /// The advantage of revealing one more slot given the probability-weighted sums over
+    /// every next buff of the child values, the success probabilities and the multipliers.
+    fn advantage_from_totals(
+        &self,
+        num_filled_slots: usize,
+        total: f64,
+        success_total: f64,
+        multiplier_total: f64,
+    ) -> f64 {
+        let num_remaining_buffs = NUM_BUFFS - num_filled_slots;
+        // The reveal is paid before the buff is known, so it costs the mean multiplier.
+        let mut expected = total / (num_remaining_buffs as f64);
+        if num_filled_slots + 1 == self.max_reveals {
+            // Full children are worth their reward, so `expected` is the reward of one draw.
+            let success = success_total / num_remaining_buffs as f64;
+            expected += (1.0 - success) * self.reforge_value(expected, success);
+        }
+        let reveal_cost = self.cost_model.weighted_reveal_cost(num_filled_slots) * multiplier_total
+            / num_remaining_buffs as f64;
+        expected - self.lambda * reveal_cost
+    }
+
+    /// The value of a failed full echo whose last reveal succeeds with probability `success`
+    /// for an expected reward of `reward`: reforging until success is worth
+    /// `(reward - lambda * reforge_cost) / success`, or 0 if abandoning is better.
+    fn reforge_value(&self, reward: f64, success: f64) -> f64 {
+        match self.reforge_cost {
+            Some(reforge_cost) if success > 0.0 => {
+                ((reward - self.lambda * reforge_cost) / success).max(0.0)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Whether the policy reforges a failed full echo reached from `(mask, score)`, where
+    /// `mask` has `max_reveals - 1` filled slots.
+    fn reforges_after(&self, mask: u16, score: u16) -> bool {
+        if self.reforge_cost.is_none() {
+            return false;
+        }
+        let num_remaining_buffs = NUM_BUFFS - calculate_num_filled_slots(mask);
+        let mut reward_total: f64 = 0.0;
+        let mut success_total: f64 = 0.0;
+        let mut remaining_buffs = MASK_ALL ^ mask;
+        while remaining_buffs != 0 {
+            let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
+            let idx = lsb.trailing_zeros() as usize;
+            remaining_buffs ^= lsb;
+
+            for j in 0..self.pmf_len[idx] {
+                let (delta, probability) = self.score_pmf_entry(idx, j);
+                let final_score = score + delta;
+                if final_score >= self.target_score {
+                    let overshoot =
+                        (final_score - self.target_score) as f64 / self.score_multiplier;
+                    reward_total +=
+                        probability * (1.0 + self.reward_slope * overshoot) * DP_VALUE_MULTIPLIER;
+                    success_total += probability;
+                }
+            }
+        }
+        let reward = reward_total / num_remaining_buffs as f64;
+        let success = success_total / num_remaining_buffs as f64;
+        self.reforge_value(reward, success) > 0.0
     }
 
     fn value_rec(&mut self, mask: u16, score: u16) -> f64 {
         let num_filled_slots = calculate_num_filled_slots(mask);
-        if num_filled_slots >= NUM_ECHO_SLOTS {
+        if num_filled_slots >= self.max_reveals {
             return if score >= self.target_score {
-                1.0 * DP_VALUE_MULTIPLIER
+                let overshoot = (score - self.target_score) as f64 / self.score_multiplier;
+                (1.0 + self.reward_slope * overshoot) * DP_VALUE_MULTIPLIER
             } else {
                 0.0
             };
@@ -613,7 +2736,8 @@ impl UpgradePolicySolver {
         let cache_index = partial_mask_to_index(mask);
 
         // Clamp score to up to target_score (but still above min_score for the mask).
-        let score = if score >= self.target_score {
+        // The reward above target_score is flat only without a reward slope.
+        let score = if score >= self.target_score && self.reward_slope == 0.0 {
             self.caches[cache_index].min_score().max(self.target_score)
         } else {
             score
@@ -629,24 +2753,7 @@ impl UpgradePolicySolver {
             return 0.0;
         }
 
-        let num_remaining_buffs = NUM_BUFFS - num_filled_slots;
-        let mut total: f64 = 0.0;
-        let mut remaining_buffs = MASK_ALL ^ mask;
-        while remaining_buffs != 0 {
-            let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
-            let idx = lsb.trailing_zeros() as usize;
-            remaining_buffs ^= lsb;
-            let next_mask = mask | (1u16 << idx);
-
-            for j in 0..self.pmf_len[idx] {
-                let (delta, probability) = self.score_pmfs[idx][j];
-                total += probability * self.value_rec(next_mask, score + delta);
-            }
-        }
-
-        let expected = total / (num_remaining_buffs as f64);
-        let advantage =
-            expected - self.lambda * self.cost_model.weighted_reveal_cost(num_filled_slots);
+        let advantage = self.continue_advantage(mask, score);
         let decision = advantage >= 0.0;
         let dp = if decision { advantage } else { 0.0 };
         self.set_cache(mask, score, dp, decision);
@@ -654,8 +2761,62 @@ impl UpgradePolicySolver {
         dp
     }
 
+    /// `value_rec` for queries: values already in the caches are reused, the rest is
+    /// computed into `memo` so the derived policy is left untouched.
+    fn value_local(&self, memo: &mut [Vec<f64>], mask: u16, score: u16) -> f64 {
+        let num_filled_slots = calculate_num_filled_slots(mask);
+        if num_filled_slots >= self.max_reveals {
+            return if score >= self.target_score {
+                let overshoot = (score - self.target_score) as f64 / self.score_multiplier;
+                (1.0 + self.reward_slope * overshoot) * DP_VALUE_MULTIPLIER
+            } else {
+                0.0
+            };
+        }
+
+        let cache_index = partial_mask_to_index(mask);
+        let cache = &self.caches[cache_index];
+        let score = if score >= self.target_score && self.reward_slope == 0.0 {
+            cache.min_score().max(self.target_score)
+        } else {
+            score
+        };
+
+        let dp_cache = cache.dp(score);
+        if !dp_cache.is_nan() {
+            return dp_cache;
+        }
+        if score + cache.best_case_remaining_score < self.target_score {
+            return 0.0;
+        }
+
+        let score_key = cache.score_to_index(score);
+        if memo[cache_index].is_empty() {
+            memo[cache_index] = vec![f64::NAN; cache.dp.len()];
+        }
+        let dp = memo[cache_index][score_key];
+        if !dp.is_nan() {
+            return dp;
+        }
+
+        let dp = self.continue_advantage_local(memo, mask, score).max(0.0);
+        memo[cache_index][score_key] = dp;
+        dp
+    }
+
     pub fn calculate_expected_resources(
         &mut self,
+    ) -> Result<ExpectedUpgradeCost, UpgradePolicySolverError> {
+        let start = Instant::now();
+        let result = self.calculate_expected_resources_impl();
+        if self.timing_enabled {
+            self.timings.expected_resources = Some(start.elapsed());
+        }
+        result
+    }
+
+    fn calculate_expected_resources_impl(
+        &mut self,
     ) -> Result<ExpectedUpgradeCost, UpgradePolicySolverError> {
         if !self.is_policy_derived {
             return Err(UpgradePolicySolverError::PolicyNotDerived);
@@ -678,6 +2839,17 @@ impl UpgradePolicySolver {
             let cut_off_score = self.caches[cache_index].cut_off_score;
             match cut_off_score {
                 None => memo.push(ExpectedCostCacheEntry::Abandon),
+                Some(cut_off_s) if self.reward_slope > 0.0 => {
+                    // The reward still grows above the target, so follow the policy there.
+                    let max_score = self.caches[cache_index].min_score() as usize
+                        + self.caches[cache_index].dp.len()
+                        - 1;
+                    let size = max_score - cut_off_s as usize + 1;
+                    memo.push(ExpectedCostCacheEntry::Reachable {
+                        cut_off_score: cut_off_s,
+                        states: vec![ExpectedUpgradeCostState::default(); size],
+                    });
+                }
                 Some(cut_off_s) => {
                     if cut_off_s < self.target_score {
                         let size = (self.target_score - cut_off_s + 1) as usize;
@@ -708,22 +2880,20 @@ impl UpgradePolicySolver {
             let next_mask = 1u16 << index;
 
             for j in 0..self.pmf_len[index] {
-                let (delta, probability) = self.score_pmfs[index][j];
+                let (delta, probability) = self.score_pmf_entry(index, j);
                 let next_state = self.expected_resources_rec(&mut memo, next_mask, delta);
 
-                total.success_probability += probability * next_state.success_probability;
-                total.tuner += probability * next_state.tuner;
-                total.exp += probability * next_state.exp;
+                total.accumulate(probability, &next_state);
             }
         }
 
-        let scale = 1.0 / NUM_BUFFS as f64;
-        total.success_probability *= scale;
-        total.tuner *= scale;
-        total.exp *= scale;
+        total.scale(1.0 / NUM_BUFFS as f64);
 
-        total.tuner += self.cost_model.tuner_cost();
-        total.exp += self.cost_model.exp_cost(0);
+        total.add_step_cost(
+            self.cost_model.tuner_cost(),
+            self.cost_model.exp_cost(0),
+            self.cost_model.raw_upgrade_exp(0, 1),
+        );
 
         match &mut memo[0] {
             ExpectedCostCacheEntry::Reachable { states, .. } => {
@@ -733,14 +2903,171 @@ impl UpgradePolicySolver {
         }
 
         self.expected_cost_cache = ExpectedCostCache::Computed(memo);
+        Ok(self.expected_upgrade_cost(&total))
+    }
 
-        Ok(ExpectedUpgradeCost {
+    /// Per-success resources of a policy whose root state is `total`.
+    fn expected_upgrade_cost(&self, total: &ExpectedUpgradeCostState) -> ExpectedUpgradeCost {
+        let keep_tuner = self
+            .cost_model
+            .success_additional_tuner_cost_at(self.max_reveals);
+        let keep_exp = self
+            .cost_model
+            .success_additional_exp_cost_at(self.max_reveals);
+        ExpectedUpgradeCost {
             success_probability: total.success_probability,
-            tuner_per_success: total.tuner / total.success_probability
-                + self.cost_model.success_additional_tuner_cost(),
-            exp_per_success: total.exp / total.success_probability
-                + self.cost_model.success_additional_exp_cost(),
-        })
+            tuner_per_success: total.tuner / total.success_probability + keep_tuner,
+            exp_per_success: total.exp / total.success_probability + keep_exp,
+            keep_tuner,
+            keep_exp,
+            tuner_exp_covariance: total.tuner_exp - total.tuner * total.exp,
+            reveals_per_attempt: total.reveals,
+            attempt_cost_mean: self
+                .cost_model
+                .weighted_attempt_cost(total.tuner, total.exp),
+            attempt_cost_variance: self.cost_model.weighted_attempt_cost_variance(
+                total.tuner_sq - total.tuner * total.tuner,
+                total.exp_sq - total.exp * total.exp,
+                total.tuner_exp - total.tuner * total.exp,
+            ),
+            success_additional_cost: self
+                .cost_model
+                .weighted_success_additional_cost_at(self.max_reveals),
+            shell_credits_per_success: self
+                .cost_model
+                .shell_credit_cost(total.raw_exp, total.reveals)
+                / total.success_probability,
+        }
+    }
+
+    /// The expected resources of continuing exactly when `decision_fn(mask, score)` is true,
+    /// instead of following the derived policy, e.g. a hand-written rule table.
+    ///
+    /// `score` is the internal score. The function is asked about every non-empty partial
+    /// mask, also at or above the target; the empty echo is always started. Reforging is
+    /// not modeled. Does not need a derived policy.
+    pub fn evaluate_policy(
+        &self,
+        decision_fn: impl Fn(u16, u16) -> bool,
+    ) -> Result<ExpectedUpgradeCost, UpgradePolicySolverError> {
+        self.evaluate_policy_impl(&decision_fn, &[])
+    }
+
+    /// The expected resources of the derived policy when every echo containing all buffs of
+    /// some mask in `forbidden` is abandoned, whatever its score and even when fully revealed.
+    ///
+    /// Each forbidden mask must be a non-empty partial mask; a single buff forbids that buff.
+    /// Like `evaluate_policy`, reforging is not modeled.
+    pub fn evaluate_with_forbidden(
+        &self,
+        forbidden: &[u16],
+    ) -> Result<ExpectedUpgradeCost, UpgradePolicySolverError> {
+        if !self.is_policy_derived() {
+            return Err(UpgradePolicySolverError::PolicyNotDerived);
+        }
+        if let Some(&mask) = forbidden
+            .iter()
+            .find(|&&mask| mask == 0 || !is_valid_external_partial_mask(mask))
+        {
+            return Err(UpgradePolicySolverError::InvalidMask { mask });
+        }
+
+        self.evaluate_policy_impl(
+            &|mask, score| {
+                self.caches[partial_mask_to_index(mask)]
+                    .get_decision(score)
+                    .unwrap_or(false)
+            },
+            forbidden,
+        )
+    }
+
+    fn evaluate_policy_impl(
+        &self,
+        decision_fn: &impl Fn(u16, u16) -> bool,
+        forbidden: &[u16],
+    ) -> Result<ExpectedUpgradeCost, UpgradePolicySolverError> {
+        let mut memo: Vec<Vec<ExpectedUpgradeCostState>> = vec![Vec::new(); NUM_PARTIAL_MASKS];
+        let total = self.evaluate_policy_rec(decision_fn, forbidden, &mut memo, 0u16, 0u16);
+        if total.success_probability <= 0.0 {
+            return Err(UpgradePolicySolverError::PolicyNeverSucceeds);
+        }
+        Ok(self.expected_upgrade_cost(&total))
+    }
+
+    /// Forbidden masks are checked first, so a forbidden echo fails even when it is fully
+    /// revealed or already at the target.
+    fn evaluate_policy_rec(
+        &self,
+        decision_fn: &impl Fn(u16, u16) -> bool,
+        forbidden: &[u16],
+        memo: &mut [Vec<ExpectedUpgradeCostState>],
+        mask: u16,
+        score: u16,
+    ) -> ExpectedUpgradeCostState {
+        let num_filled_slots = calculate_num_filled_slots(mask);
+        if forbidden
+            .iter()
+            .any(|&forbidden_mask| (forbidden_mask & !mask) == 0)
+        {
+            return ExpectedUpgradeCostState::abandoned_state(&self.cost_model, num_filled_slots);
+        }
+        if num_filled_slots >= self.max_reveals {
+            return if score >= self.target_score {
+                ExpectedUpgradeCostState {
+                    success_probability: 1.0,
+                    ..Default::default()
+                }
+            } else {
+                ExpectedUpgradeCostState::abandoned_state(&self.cost_model, num_filled_slots)
+            };
+        }
+        if mask != 0 && !decision_fn(mask, score) {
+            return ExpectedUpgradeCostState::abandoned_state(&self.cost_model, num_filled_slots);
+        }
+
+        let cache_index = partial_mask_to_index(mask);
+        let score_key = self.caches[cache_index].score_to_index(score);
+        if memo[cache_index].is_empty() {
+            memo[cache_index] =
+                vec![ExpectedUpgradeCostState::default(); self.caches[cache_index].dp.len()];
+        }
+        let state = memo[cache_index][score_key];
+        if !state.success_probability.is_nan() {
+            return state;
+        }
+
+        let num_remaining_buffs = NUM_BUFFS - num_filled_slots;
+        let mut total = ExpectedUpgradeCostState::failed_state();
+        let mut remaining_buffs = MASK_ALL ^ mask;
+        while remaining_buffs != 0 {
+            let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
+            let index = lsb.trailing_zeros() as usize;
+            remaining_buffs ^= lsb;
+            let next_mask = mask | (1u16 << index);
+
+            for j in 0..self.pmf_len[index] {
+                let (delta, probability) = self.score_pmf_entry(index, j);
+                let next_state = self.evaluate_policy_rec(
+                    decision_fn,
+                    forbidden,
+                    memo,
+                    next_mask,
+                    score + delta,
+                );
+                total.accumulate(probability, &next_state);
+            }
+        }
+        total.scale(1.0 / num_remaining_buffs as f64);
+        total.add_step_cost(
+            self.cost_model.tuner_cost(),
+            self.cost_model.exp_cost(num_filled_slots),
+            self.cost_model
+                .raw_upgrade_exp(num_filled_slots, num_filled_slots + 1),
+        );
+
+        memo[cache_index][score_key] = total;
+        total
     }
 
     fn expected_resources_rec(
@@ -750,32 +3077,45 @@ impl UpgradePolicySolver {
         score: u16,
     ) -> ExpectedUpgradeCostState {
         let num_filled_slots = calculate_num_filled_slots(mask);
-        if num_filled_slots >= NUM_ECHO_SLOTS {
-            return ExpectedUpgradeCostState {
-                success_probability: if score >= self.target_score { 1.0 } else { 0.0 },
-                ..Default::default()
+        if num_filled_slots >= self.max_reveals {
+            return if score >= self.target_score {
+                ExpectedUpgradeCostState {
+                    success_probability: 1.0,
+                    ..Default::default()
+                }
+            } else {
+                ExpectedUpgradeCostState::abandoned_state(&self.cost_model, num_filled_slots)
             };
         }
 
         let cache_index = partial_mask_to_index(mask);
         let score_key = match &memo[cache_index] {
             ExpectedCostCacheEntry::Abandon => {
-                return ExpectedUpgradeCostState::failed_state();
+                return ExpectedUpgradeCostState::abandoned_state(
+                    &self.cost_model,
+                    num_filled_slots,
+                );
             }
             ExpectedCostCacheEntry::Reachable {
                 cut_off_score,
                 states,
             } => {
                 if score < *cut_off_score {
-                    return ExpectedUpgradeCostState::failed_state();
+                    return ExpectedUpgradeCostState::abandoned_state(
+                        &self.cost_model,
+                        num_filled_slots,
+                    );
                 }
-                if score >= self.target_score {
+                // With a flat reward every continuation from here succeeds.
+                if score >= self.target_score && self.reward_slope == 0.0 {
                     return ExpectedUpgradeCostState::guaranteed_success_state(
                         &self.cost_model,
                         num_filled_slots,
+                        self.max_reveals,
                     );
                 }
-                // Memo indexing path: cut_off_score <= score < target_score.
+                // Memo indexing path: cut_off_score <= score, and score < target_score
+                // unless the reward has a slope.
                 let score_key = (score - *cut_off_score) as usize;
                 let state = states[score_key];
                 if !state.success_probability.is_nan() {
@@ -786,6 +3126,8 @@ impl UpgradePolicySolver {
         };
 
         let num_remaining_buffs = NUM_BUFFS - num_filled_slots;
+        // Failed full echoes are reforged until they succeed, at no tuner or exp.
+        let reforges = num_filled_slots + 1 == self.max_reveals && self.reforges_after(mask, score);
         let mut total = ExpectedUpgradeCostState::failed_state();
         let mut remaining_buffs = MASK_ALL ^ mask;
         while remaining_buffs != 0 {
@@ -795,22 +3137,28 @@ impl UpgradePolicySolver {
             let next_mask = mask | (1u16 << index);
 
             for j in 0..self.pmf_len[index] {
-                let (delta, probability) = self.score_pmfs[index][j];
-                let next_state = self.expected_resources_rec(memo, next_mask, score + delta);
+                let (delta, probability) = self.score_pmf_entry(index, j);
+                let next_state = if reforges {
+                    ExpectedUpgradeCostState {
+                        success_probability: 1.0,
+                        ..Default::default()
+                    }
+                } else {
+                    self.expected_resources_rec(memo, next_mask, score + delta)
+                };
 
-                total.success_probability += probability * next_state.success_probability;
-                total.tuner += probability * next_state.tuner;
-                total.exp += probability * next_state.exp;
+                total.accumulate(probability, &next_state);
             }
         }
 
-        let scale = 1.0 / num_remaining_buffs as f64;
-        total.success_probability *= scale;
-        total.tuner *= scale;
-        total.exp *= scale;
+        total.scale(1.0 / num_remaining_buffs as f64);
 
-        total.tuner += self.cost_model.tuner_cost();
-        total.exp += self.cost_model.exp_cost(num_filled_slots);
+        total.add_step_cost(
+            self.cost_model.tuner_cost(),
+            self.cost_model.exp_cost(num_filled_slots),
+            self.cost_model
+                .raw_upgrade_exp(num_filled_slots, num_filled_slots + 1),
+        );
 
         match &mut memo[cache_index] {
             ExpectedCostCacheEntry::Reachable {
@@ -823,4 +3171,254 @@ impl UpgradePolicySolver {
         }
         total
     }
+
+    /// Probability of reaching target_score when every remaining slot is revealed.
+    fn full_reveal_success_rec(&self, memo: &mut [Vec<f64>], mask: u16, score: u16) -> f64 {
+        if score >= self.target_score {
+            return 1.0;
+        }
+        let num_filled_slots = calculate_num_filled_slots(mask);
+        if num_filled_slots >= self.max_reveals {
+            return 0.0;
+        }
+
+        let cache_index = partial_mask_to_index(mask);
+        let cache = &self.caches[cache_index];
+        if score + cache.best_case_remaining_score < self.target_score {
+            return 0.0;
+        }
+        let score_key = cache.score_to_index(score);
+        if memo[cache_index].is_empty() {
+            memo[cache_index] = vec![f64::NAN; cache.dp.len()];
+        }
+        let probability = memo[cache_index][score_key];
+        if !probability.is_nan() {
+            return probability;
+        }
+
+        let num_remaining_buffs = NUM_BUFFS - num_filled_slots;
+        let mut total: f64 = 0.0;
+        let mut remaining_buffs = MASK_ALL ^ mask;
+        while remaining_buffs != 0 {
+            let lsb = remaining_buffs & remaining_buffs.wrapping_neg();
+            let index = lsb.trailing_zeros() as usize;
+            remaining_buffs ^= lsb;
+            let next_mask = mask | (1u16 << index);
+
+            for j in 0..self.pmf_len[index] {
+                let (delta, probability) = self.score_pmf_entry(index, j);
+                total += probability * self.full_reveal_success_rec(memo, next_mask, score + delta);
+            }
+        }
+
+        let probability = total / num_remaining_buffs as f64;
+        memo[cache_index][score_key] = probability;
+        probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FixedScorer, LinearScorer, PartialMask};
+
+    fn uniform_scorer() -> LinearScorer {
+        LinearScorer::default([1.0; NUM_BUFFS]).unwrap()
+    }
+
+    /// Every buff scores 0.01 display points except the last, which scores 50.
+    fn jackpot_fixed_scorer() -> FixedScorer {
+        let mut weights = [1u16; NUM_BUFFS];
+        weights[NUM_BUFFS - 1] = 5000;
+        FixedScorer::new(weights).unwrap()
+    }
+
+    #[test]
+    fn target_score_above_u16_range_is_rejected() {
+        let result =
+            UpgradePolicySolver::new(&uniform_scorer(), false, 1000.0, CostModel::tuner_only());
+        assert!(matches!(
+            result,
+            Err(UpgradePolicySolverError::TargetScoreOutOfRange { target_score_display })
+                if target_score_display == 1000.0
+        ));
+
+        let mut solver =
+            UpgradePolicySolver::new(&uniform_scorer(), false, 60.0, CostModel::tuner_only())
+                .unwrap();
+        assert!(matches!(
+            solver.update_target_score_display(1000.0),
+            Err(UpgradePolicySolverError::TargetScoreOutOfRange { .. })
+        ));
+        assert_eq!(solver.target_score, 6000);
+    }
+
+    #[test]
+    fn linear_reward_resources_follow_abandon_above_target() {
+        // Every echo passes the target, but only the rare jackpot buff is worth much, so
+        // echoes that have not rolled it after a few reveals are not worth finishing.
+        let mut solver = UpgradePolicySolver::new_with_linear_reward(
+            &jackpot_fixed_scorer(),
+            false,
+            0.01,
+            CostModel::new(0.0, 0.0, 1.0, 0.0).unwrap(),
+            1.0,
+        )
+        .unwrap();
+        solver.lambda_search(1e-9, 200).unwrap();
+
+        let target = solver.target_score;
+        let abandons_above_target = PARTIAL_MASKS.iter().any(|&mask| {
+            mask != 0
+                && solver.caches[partial_mask_to_index(mask)]
+                    .cut_off_score
+                    .is_none_or(|cut_off| cut_off > target)
+        });
+        assert!(abandons_above_target);
+
+        let expected = solver.calculate_expected_resources().unwrap();
+        assert!(expected.success_probability() < 1.0);
+        let evaluated = solver
+            .evaluate_policy(|mask, score| {
+                solver.caches[partial_mask_to_index(mask)]
+                    .get_decision(score)
+                    .unwrap_or(false)
+            })
+            .unwrap();
+        assert!((evaluated.success_probability() - expected.success_probability()).abs() < 1e-12);
+        assert!((evaluated.tuner_per_success() - expected.tuner_per_success()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weight_gradient_rejects_a_different_scorer() {
+        let scorer = LinearScorer::new([1.0; NUM_BUFFS], 10.0, 100.0).unwrap();
+        let solver =
+            UpgradePolicySolver::new(&scorer, false, 30.0, CostModel::tuner_only()).unwrap();
+        let result =
+            solver.weight_gradient(&[1.0; NUM_BUFFS], LinearScorer::default, 0.1, 1e-6, 100);
+        assert!(matches!(
+            result,
+            Err(UpgradePolicySolverError::ScorerMismatch)
+        ));
+    }
+
+    #[test]
+    fn weight_gradient_uses_the_given_scorer() {
+        let mut weights = [100u16; NUM_BUFFS];
+        weights[NUM_BUFFS - 1] = 1000;
+        let scorer = FixedScorer::new(weights).unwrap();
+        let solver =
+            UpgradePolicySolver::new(&scorer, false, 6.0, CostModel::tuner_only()).unwrap();
+        let build_scorer = |weights: [f64; NUM_BUFFS]| FixedScorer::new(weights.map(|w| w as u16));
+
+        let gradient = solver
+            .weight_gradient(&weights.map(f64::from), build_scorer, 100.0, 1e-9, 200)
+            .unwrap();
+        // Only echoes with the last buff reach the target. Raising any other weight by one
+        // point lets echoes with that buff reach it too; the last buff is already enough.
+        assert!(
+            gradient[..NUM_BUFFS - 1]
+                .iter()
+                .all(|&derivative| derivative < 0.0)
+        );
+        assert_eq!(gradient[NUM_BUFFS - 1], 0.0);
+    }
+
+    #[test]
+    fn lambda_search_expansion_limit() {
+        // A nearly free reveal puts the root lambda far above the initial bracket of 1.
+        let cost_model = CostModel::new(0.0, 1e-6, 0.0, 0.0).unwrap();
+        let mut solver =
+            UpgradePolicySolver::new(&jackpot_fixed_scorer(), false, 50.0, cost_model).unwrap();
+
+        solver.set_max_lambda_expansions(4);
+        assert!(matches!(
+            solver.lambda_search(1e-9, 200),
+            Err(UpgradePolicySolverError::LambdaBracketExpansionExhausted { attempts: 4 })
+        ));
+
+        solver.set_max_lambda_expansions(DEFAULT_MAX_LAMBDA_EXPANSIONS);
+        let lambda = solver.lambda_search(1e-9, 200).unwrap();
+        assert!(lambda > 16.0);
+    }
+
+    #[test]
+    fn from_pmfs_tolerance_boundary() {
+        // Every PMF sums to 1 + 1e-6.
+        let score_pmfs = || vec![vec![(100, 0.5), (200, 0.5 + 1e-6)]; NUM_BUFFS];
+
+        assert!(matches!(
+            UpgradePolicySolver::from_pmfs(score_pmfs(), 5.0, CostModel::tuner_only(), 0.9e-6),
+            Err(UpgradePolicySolverError::InvalidScorePmfNotNormalized { buff_index: 0, .. })
+        ));
+        assert!(matches!(
+            UpgradePolicySolver::from_pmfs(
+                score_pmfs(),
+                5.0,
+                CostModel::tuner_only(),
+                DEFAULT_PMF_SUM_TOL
+            ),
+            Err(UpgradePolicySolverError::InvalidScorePmfNotNormalized { .. })
+        ));
+        assert!(
+            UpgradePolicySolver::from_pmfs(score_pmfs(), 5.0, CostModel::tuner_only(), 1.1e-6)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn decision_regret_leaves_the_caches_untouched() {
+        let mut solver = UpgradePolicySolver::new(
+            &jackpot_fixed_scorer(),
+            false,
+            50.0,
+            CostModel::tuner_only(),
+        )
+        .unwrap();
+        solver.lambda_search(1e-9, 200).unwrap();
+        let snapshot = |solver: &UpgradePolicySolver| {
+            solver
+                .caches
+                .iter()
+                .map(|cache| {
+                    let dp: Vec<u64> = cache.dp.iter().map(|value| value.to_bits()).collect();
+                    (dp, cache.touched.clone(), cache.cut_off_score)
+                })
+                .collect::<Vec<_>>()
+        };
+        let before = snapshot(&solver);
+
+        // Four low buffs: probably never reached by the policy.
+        let mask = PartialMask::new(0b1111).unwrap();
+        let score = RawScore::new(4);
+        let continue_regret = solver.decision_regret(mask, score, true).unwrap();
+        let abandon_regret = solver.decision_regret(mask, score, false).unwrap();
+        assert!(continue_regret >= 0.0 && abandon_regret >= 0.0);
+        assert!(continue_regret == 0.0 || abandon_regret == 0.0);
+        assert!(continue_regret + abandon_regret > 0.0);
+        assert!(snapshot(&solver) == before);
+    }
+
+    #[test]
+    fn forbidden_buff_fails_even_above_target() {
+        let mut solver = UpgradePolicySolver::new(
+            &jackpot_fixed_scorer(),
+            false,
+            50.0,
+            CostModel::tuner_only(),
+        )
+        .unwrap();
+        solver.lambda_search(1e-9, 200).unwrap();
+        let jackpot = 1u16 << (NUM_BUFFS - 1);
+
+        // Only the jackpot buff reaches the target, also when it is revealed last.
+        assert!(matches!(
+            solver.evaluate_with_forbidden(&[jackpot]),
+            Err(UpgradePolicySolverError::PolicyNeverSucceeds)
+        ));
+
+        let unrestricted = solver.evaluate_with_forbidden(&[]).unwrap();
+        let restricted = solver.evaluate_with_forbidden(&[0b1]).unwrap();
+        assert!(restricted.success_probability < unrestricted.success_probability);
+    }
 }